@@ -1,2 +1,3 @@
 #![no_std]
 pub mod traits;
+pub mod types;
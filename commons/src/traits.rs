@@ -1,30 +1,62 @@
+use crate::types::{Signer, SignerKey};
 use soroban_sdk::{Address, BytesN, Env, Symbol, Val, Vec};
 
 /// The `MultiCliquePolicyTrait` defines the interface for interacting with the MultiClique policy system.
 /// It provides methods to get the required signing threshold for executing a function and to run the policy
 /// for a given function call. The trait is designed to offer flexibility and adaptability for various policy requirements.
+///
+/// Every method receives the full signer set as well as the subset that actually signed, so policies can
+/// require specific signers, e.g. a CFO co-signing transfers above a certain amount.
 pub trait MultiCliquePolicyTrait {
     /// Determines the required threshold of signers for a specific function call.
     ///
     /// # Parameters
     /// * `env`: The environment that provides access to the contract's storage and other functionalities.
-    /// * `num_signers`: The total number of signers in the MultiClique system.
-    /// * `signers`: A vector of signers represented by their public keys.
+    /// * `signed_weight`: The summed weight of all valid signatures submitted for the call.
+    /// * `signers`: A vector of all signers of the MultiClique, including their weights.
+    /// * `signed`: The keys of the signers whose signatures have been verified for the call.
     /// * `address`: The address of the contract that the function belongs to.
     /// * `fn_name`: The symbol representing the function name.
     /// * `args`: A vector of values representing the arguments for the function call.
     ///
     /// # Returns
-    /// Returns the required number of signers (threshold) to execute the specified function.
+    /// Returns the required signing weight (threshold) to execute the specified function.
     fn get_threshold(
         env: Env,
-        num_signers: u32,
-        signers: Vec<BytesN<32>>,
+        signed_weight: u32,
+        signers: Vec<Signer>,
+        signed: Vec<SignerKey>,
         address: Address,
         fn_name: Symbol,
         args: Vec<Val>,
     ) -> u32;
 
+    /// Determines the cliques that have to reach their quorum for a specific function call.
+    ///
+    /// Cliques are named signer groups managed by the MultiClique; every returned clique has to be met
+    /// in addition to the threshold returned by `get_threshold`.
+    ///
+    /// # Parameters
+    /// * `env`: The environment that provides access to the contract's storage and other functionalities.
+    /// * `signed_weight`: The summed weight of all valid signatures submitted for the call.
+    /// * `signers`: A vector of all signers of the MultiClique, including their weights.
+    /// * `signed`: The keys of the signers whose signatures have been verified for the call.
+    /// * `address`: The address of the contract that the function belongs to.
+    /// * `fn_name`: The symbol representing the function name.
+    /// * `args`: A vector of values representing the arguments for the function call.
+    ///
+    /// # Returns
+    /// Returns the names of the required cliques, or an empty vector if none are required.
+    fn get_required_cliques(
+        env: Env,
+        signed_weight: u32,
+        signers: Vec<Signer>,
+        signed: Vec<SignerKey>,
+        address: Address,
+        fn_name: Symbol,
+        args: Vec<Val>,
+    ) -> Vec<Symbol>;
+
     /// Executes the policy logic for a given function call.
     ///
     /// This method runs the policy for a specific function, taking into account the signers, address, function name, and arguments.
@@ -32,17 +64,64 @@ pub trait MultiCliquePolicyTrait {
     ///
     /// # Parameters
     /// * `env`: The environment that provides access to the contract's storage and other functionalities.
-    /// * `num_signers`: The total number of signers in the MultiClique system.
-    /// * `signers`: A vector of signers represented by their public keys.
+    /// * `signed_weight`: The summed weight of all valid signatures submitted for the call.
+    /// * `signers`: A vector of all signers of the MultiClique, including their weights.
+    /// * `signed`: The keys of the signers whose signatures have been verified for the call.
     /// * `address`: The address of the contract that the function belongs to.
     /// * `fn_name`: The symbol representing the function name.
     /// * `args`: A vector of values representing the arguments for the function call.
     fn run_policy(
         env: Env,
-        num_signers: u32,
-        signers: Vec<BytesN<32>>,
+        signed_weight: u32,
+        signers: Vec<Signer>,
+        signed: Vec<SignerKey>,
         address: Address,
         fn_name: Symbol,
         args: Vec<Val>,
     );
 }
+
+/// The `MultiCliqueDeployPolicyTrait` defines the interface of policies guarding contract deployments
+/// authorized by the MultiClique, e.g. to only allow deploying reviewed wasm hashes.
+pub trait MultiCliqueDeployPolicyTrait {
+    /// Determines the required threshold of signers for a contract deployment.
+    ///
+    /// # Parameters
+    /// * `env`: The environment that provides access to the contract's storage and other functionalities.
+    /// * `signed_weight`: The summed weight of all valid signatures submitted for the deployment.
+    /// * `signers`: A vector of all signers of the MultiClique, including their weights.
+    /// * `signed`: The keys of the signers whose signatures have been verified for the deployment.
+    /// * `wasm_hash`: The hash of the wasm code the contract is created from.
+    /// * `salt`: The salt the contract address is derived from.
+    ///
+    /// # Returns
+    /// Returns the required signing weight (threshold) to deploy the contract.
+    fn get_deployment_threshold(
+        env: Env,
+        signed_weight: u32,
+        signers: Vec<Signer>,
+        signed: Vec<SignerKey>,
+        wasm_hash: BytesN<32>,
+        salt: BytesN<32>,
+    ) -> u32;
+
+    /// Executes the policy logic for a contract deployment.
+    ///
+    /// Implementations reject a deployment by panicking, e.g. if the wasm hash is not allowlisted.
+    ///
+    /// # Parameters
+    /// * `env`: The environment that provides access to the contract's storage and other functionalities.
+    /// * `signed_weight`: The summed weight of all valid signatures submitted for the deployment.
+    /// * `signers`: A vector of all signers of the MultiClique, including their weights.
+    /// * `signed`: The keys of the signers whose signatures have been verified for the deployment.
+    /// * `wasm_hash`: The hash of the wasm code the contract is created from.
+    /// * `salt`: The salt the contract address is derived from.
+    fn run_deployment_policy(
+        env: Env,
+        signed_weight: u32,
+        signers: Vec<Signer>,
+        signed: Vec<SignerKey>,
+        wasm_hash: BytesN<32>,
+        salt: BytesN<32>,
+    );
+}
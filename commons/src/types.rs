@@ -0,0 +1,31 @@
+use soroban_sdk::{contracttype, Address, BytesN};
+
+/// The key a signer of a MultiClique account signs with.
+///
+/// The ordering of keys (variant first, then key bytes) defines the order in which
+/// signatures have to be submitted to `__check_auth`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum SignerKey {
+    /// A raw ed25519 public key.
+    Ed25519(BytesN<32>),
+    /// An uncompressed SEC1 encoded secp256r1 public key, as used by WebAuthn passkeys.
+    Secp256r1(BytesN<65>),
+    /// Any address, e.g. another MultiClique or a DAO contract, approving via `require_auth_for_args`.
+    Address(Address),
+}
+
+/// A signer of a MultiClique account together with its voting weight.
+///
+/// Thresholds are compared against the summed weight of all valid signatures,
+/// so a signer with a weight of 3 counts as much as three signers with a weight of 1.
+///
+/// Signers with an expiry lose their signing rights once the ledger sequence reaches `expires_at`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signer {
+    pub key: SignerKey,
+    pub weight: u32,
+    /// The ledger sequence from which on the signer is expired, or 0 if it never expires.
+    pub expires_at: u32,
+}
@@ -4,10 +4,10 @@ use soroban_sdk::contracterror;
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum MultiCliqueError {
-    /// Occurs in `attach_policy` when a policy already exists for the given context.
+    /// Occurs in `attach_policy` when the policy is already attached to the given context.
     ContractPolicyExists = 1000,
 
-    /// Occurs in `detach_policy` when a policy does not exist for the given context.
+    /// Occurs in `detach_policy`, `remove_policy`, `remove_fallback_policy` and `remove_deploy_policy` when a policy does not exist for the given context.
     ContractPolicyDoesNotExist = 1001,
 
     /// Occurs if the maximum supported number of signers has been reached
@@ -16,10 +16,10 @@ pub enum MultiCliqueError {
     /// Occurs in `__check_auth` when a public key in the signed messages is not found among the authorized signers.
     UnknownSigner = 1003,
 
-    /// Occurs in `__check_auth` if the number of signers does not meet the default threshold for authorization.
+    /// Occurs in `__check_auth` if the weight of the signers does not meet the default threshold for authorization.
     DefaultThresholdNotMet = 1004,
 
-    /// Occurs in `__check_auth` if the number of signers does not meet the threshold defined by a specific policy for authorization.
+    /// Occurs in `__check_auth` if the weight of the signers does not meet the threshold defined by a specific policy for authorization.
     PolicyThresholdNotMet = 1005,
 
     /// Occurs in `remove_signer` if an attempt is made to remove a signer that does not exist in the list of authorized signers.
@@ -28,9 +28,109 @@ pub enum MultiCliqueError {
     /// Occurs in `init` if the contract has already been initialized.
     AlreadyInitialized = 1007,
 
-    /// Occurs if the threshold exceeds the number of signers
+    /// Occurs if the threshold exceeds the summed weight of all signers
     InvalidThreshold = 1008,
 
     /// Occurs if a signer should be added multiple times
     SignerAlreadyAdded = 1009,
+
+    /// Occurs in `init` and `add_signer` if a signer has a weight of zero.
+    InvalidSignerWeight = 1010,
+
+    /// Occurs in `create_clique` if a clique with the given name already exists.
+    CliqueExists = 1011,
+
+    /// Occurs if a clique is referenced by name that does not exist.
+    CliqueDoesNotExist = 1012,
+
+    /// Occurs in `__check_auth` if a required clique did not reach its quorum among the signers.
+    CliqueQuorumNotMet = 1013,
+
+    /// Occurs in `delete_clique` if the clique is still required by the default rule.
+    CliqueInUse = 1014,
+
+    /// Occurs in `__check_auth` if the same public key submitted more than one signature.
+    DuplicateSigner = 1015,
+
+    /// Occurs in `__check_auth` if the signed messages are not sorted by public key.
+    UnsortedSignatures = 1016,
+
+    /// Occurs in `__check_auth` if a passkey signature or its authenticator data is invalid.
+    InvalidSignature = 1017,
+
+    /// Occurs in `__check_auth` if the challenge of a passkey assertion is not the signature payload.
+    ChallengeMismatch = 1018,
+
+    /// Occurs in `attach_policy` if the maximum supported number of policies per context has been reached.
+    PolicyLimitExceeded = 1019,
+
+    /// Occurs in `reorder_policies` if the new order is not a permutation of the attached policies.
+    InvalidPolicyOrder = 1020,
+
+    /// Occurs in strict mode if a contract without a policy for the call is not on the allowlist.
+    ContractNotAllowed = 1021,
+
+    /// Occurs if a contract deployment doesn't meet the deploy threshold or the threshold of the deploy policy.
+    DeployThresholdNotMet = 1022,
+
+    /// Occurs if a change to the MultiClique itself doesn't meet the governance threshold.
+    GovThresholdNotMet = 1023,
+
+    /// Occurs in `initiate_recovery` if one of the given addresses is not a guardian.
+    NotAGuardian = 1024,
+
+    /// Occurs if a guardian is given multiple times.
+    DuplicateGuardian = 1025,
+
+    /// Occurs in `initiate_recovery` if not enough guardians approved the recovery.
+    GuardianThresholdNotMet = 1026,
+
+    /// Occurs in `initiate_recovery` if another recovery is already pending.
+    RecoveryPending = 1027,
+
+    /// Occurs in `cancel_recovery` and `execute_recovery` if no recovery is pending.
+    NoRecoveryPending = 1028,
+
+    /// Occurs in `execute_recovery` if the recovery delay has not passed yet.
+    RecoveryDelayNotPassed = 1029,
+
+    /// Occurs if a signer or session key should be added with an expiry ledger that has already passed.
+    InvalidSignerExpiry = 1030,
+
+    /// Occurs in `add_session_key` if the session key has already been added.
+    SessionKeyExists = 1031,
+
+    /// Occurs in `remove_session_key` if the session key does not exist.
+    SessionKeyDoesNotExist = 1032,
+
+    /// Occurs if a session key has expired.
+    SessionKeyExpired = 1033,
+
+    /// Occurs if a session key should authorize a call outside of its scope, or be scoped to the MultiClique itself.
+    SessionKeyOutOfScope = 1034,
+
+    /// Occurs if a session key has used up its call budget.
+    SessionBudgetExhausted = 1035,
+
+    /// Occurs if anything but unfreezing, rotating signers or cancelling a recovery should be authorized while the
+    /// MultiClique is frozen.
+    Frozen = 1036,
+
+    /// Occurs if unfreezing or rotating signers while frozen doesn't meet the unfreeze threshold.
+    UnfreezeThresholdNotMet = 1037,
+
+    /// Occurs in `set_spend_limit` if the limit is negative or the period is empty.
+    InvalidSpendLimit = 1038,
+
+    /// Occurs in `remove_spend_limit` if the token has no spend limit.
+    SpendLimitDoesNotExist = 1039,
+
+    /// Occurs in `__check_auth` if the client data of a passkey assertion is malformed or not of type `webauthn.get`.
+    InvalidClientData = 1040,
+
+    /// Occurs if a passkey signer is used although the contract was built without the `passkeys` feature.
+    PasskeysDisabled = 1041,
+
+    /// Occurs in `set_guardians` if the recovery delay is shorter than `MIN_RECOVERY_DELAY`.
+    InvalidRecoveryDelay = 1042,
 }
@@ -1,3 +1,5 @@
+use crate::SessionScope;
+use commons::types::{Signer, SignerKey};
 use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Symbol, Vec};
 
 // Symbol representing the core contract init.
@@ -9,6 +11,39 @@ pub const SIGNER: Symbol = symbol_short!("SIGNER");
 // Symbol representing policy-related events.
 pub const POLICY: Symbol = symbol_short!("POLICY");
 
+// Symbol representing policy-stack-related events.
+pub const STACK: Symbol = symbol_short!("STACK");
+
+// Symbol representing fallback-policy-related events.
+pub const FALLBACK: Symbol = symbol_short!("FALLBACK");
+
+// Symbol representing strict-mode-related events.
+pub const STRICT: Symbol = symbol_short!("STRICT");
+
+// Symbol representing allowlist-related events.
+pub const ALLOW: Symbol = symbol_short!("ALLOW");
+
+// Symbol representing deployment-related events.
+pub const DEPLOY: Symbol = symbol_short!("DEPLOY");
+
+// Symbol representing guardian-related events.
+pub const GUARDIAN: Symbol = symbol_short!("GUARDIAN");
+
+// Symbol representing recovery-related events.
+pub const RECOVERY: Symbol = symbol_short!("RECOVERY");
+
+// Symbol representing session-key-related events.
+pub const SESSION: Symbol = symbol_short!("SESSION");
+
+// Symbol representing spend-limit-related events.
+pub const LIMIT: Symbol = symbol_short!("LIMIT");
+
+// Symbol representing clique-related events.
+pub const CLIQUE: Symbol = symbol_short!("CLIQUE");
+
+// Symbol representing clique-membership-related events.
+pub const MEMBER: Symbol = symbol_short!("MEMBER");
+
 // Symbol representing governance-related events.
 pub const GOV: Symbol = symbol_short!("GOV");
 
@@ -21,25 +56,58 @@ pub const REMOVED: Symbol = symbol_short!("removed");
 // Symbol representing a changed event.
 pub const CHANGED: Symbol = symbol_short!("changed");
 
+// Symbol representing a contract code upgrade.
+pub const UPGRADED: Symbol = symbol_short!("upgraded");
+
+// Symbol representing a storage migration.
+pub const MIGRATED: Symbol = symbol_short!("migrated");
+
+// Symbol representing a change of the default rule.
+pub const DEFAULT: Symbol = symbol_short!("default");
+
+// Symbol representing a change of the governance threshold.
+pub const THRESHOLD: Symbol = symbol_short!("threshold");
+
+// Symbol representing the replacement of a signer.
+pub const REPLACED: Symbol = symbol_short!("replaced");
+
+// Symbol representing a batch update of the configuration.
+pub const UPDATED: Symbol = symbol_short!("updated");
+
+// Symbol representing the start of a process.
+pub const INITIATED: Symbol = symbol_short!("initiated");
+
+// Symbol representing the cancellation of a process.
+pub const CANCELLED: Symbol = symbol_short!("cancelled");
+
+// Symbol representing the completion of a process.
+pub const EXECUTED: Symbol = symbol_short!("executed");
+
+// Symbol representing the freezing of the multiclique.
+pub const FROZEN: Symbol = symbol_short!("frozen");
+
+// Symbol representing the unfreezing of the multiclique.
+pub const UNFROZEN: Symbol = symbol_short!("unfrozen");
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct InitEventData {
     pub threshold: u32,
-    pub signers: Vec<BytesN<32>>,
+    pub signers: Vec<Signer>,
 }
 
 // Event data for when a signer is added.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SignerAddedEventData {
-    pub signer: BytesN<32>,
+    pub signer: Signer,
 }
 
 // Event data for when a signer is removed.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SignerRemovedEventData {
-    pub signer: BytesN<32>,
+    pub signer: SignerKey,
 }
 
 // Event data for when a policy is added along with its associated context.
@@ -48,6 +116,8 @@ pub struct SignerRemovedEventData {
 pub struct PolicyAddedEventData {
     pub policy: Address,
     pub context: Vec<Address>,
+    // empty if the policy is bound to the contexts as a whole
+    pub fn_names: Vec<Symbol>,
 }
 
 // Event data for when a policy is removed from its associated context.
@@ -55,6 +125,199 @@ pub struct PolicyAddedEventData {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PolicyRemovedEventData {
     pub context: Vec<Address>,
+    // empty if the contract-wide policies were removed
+    pub fn_names: Vec<Symbol>,
+}
+
+// Event data for when a single policy is removed from the stack of its associated context.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolicyStackRemovedEventData {
+    pub policy: Address,
+    pub context: Vec<Address>,
+    pub fn_names: Vec<Symbol>,
+}
+
+// Event data for when the policies of a context are reordered.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolicyStackChangedEventData {
+    pub context: Address,
+    // the reordered function, empty if the contract-wide policies were reordered
+    pub fn_names: Vec<Symbol>,
+    pub policies: Vec<Address>,
+}
+
+// Event data for when the fallback policy is set or replaced.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FallbackPolicyChangedEventData {
+    pub policy: Address,
+}
+
+// Event data for when the fallback policy is removed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FallbackPolicyRemovedEventData {
+    pub policy: Address,
+}
+
+// Event data for when strict mode is enabled or disabled.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StrictModeChangedEventData {
+    pub enabled: bool,
+}
+
+// Event data for when contracts are added to the strict mode allowlist.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowlistAddedEventData {
+    pub contracts: Vec<Address>,
+}
+
+// Event data for when contracts are removed from the strict mode allowlist.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowlistRemovedEventData {
+    pub contracts: Vec<Address>,
+}
+
+// Event data for when the governance threshold is changed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GovThresholdChangedEventData {
+    pub threshold: u32,
+}
+
+// Event data for when the deploy threshold is changed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeployThresholdChangedEventData {
+    pub threshold: u32,
+}
+
+// Event data for when the deploy policy is set or replaced.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeployPolicyAddedEventData {
+    pub policy: Address,
+}
+
+// Event data for when the deploy policy is removed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeployPolicyRemovedEventData {
+    pub policy: Address,
+}
+
+// Event data for when a signer is replaced by another one.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignerReplacedEventData {
+    pub old: SignerKey,
+    pub new: Signer,
+}
+
+// Event data summarizing a batch update of the configuration.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigUpdatedEventData {
+    pub operations: u32,
+    pub signers: u32,
+    pub threshold: u32,
+}
+
+// Event data for when the guardians, their threshold or the recovery delay are changed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GuardiansChangedEventData {
+    pub guardians: Vec<Address>,
+    pub threshold: u32,
+    pub delay: u32,
+}
+
+// Event data for when guardians initiate the replacement of the signer set.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoveryInitiatedEventData {
+    pub guardians: Vec<Address>,
+    pub signers: Vec<Signer>,
+    pub threshold: u32,
+    pub executable_at: u32,
+}
+
+// Event data for when the signers cancel a pending recovery.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoveryCancelledEventData {
+    pub signers: Vec<Signer>,
+    pub threshold: u32,
+}
+
+// Event data for when a recovery replaced the signer set.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoveryExecutedEventData {
+    pub signers: Vec<Signer>,
+    pub threshold: u32,
+}
+
+// Event data for when a session key is added.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionKeyAddedEventData {
+    pub public_key: BytesN<32>,
+    pub scope: Vec<SessionScope>,
+    pub expires_at: u32,
+    // u32::MAX if the session key has no call budget
+    pub budget: u32,
+}
+
+// Event data for when a session key is removed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionKeyRemovedEventData {
+    pub public_key: BytesN<32>,
+}
+
+// Event data for when the multiclique is frozen.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FrozenEventData {
+    // the ledger the multiclique unfreezes at, 0 if it stays frozen until unfrozen
+    pub until: u32,
+}
+
+// Event data for when the multiclique is unfrozen.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnfrozenEventData {
+    pub ledger: u32,
+}
+
+// Event data for when the threshold required while frozen is changed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnfreezeThresholdChangedEventData {
+    pub threshold: u32,
+}
+
+// Event data for when the spend limit of a token is set or changed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpendLimitChangedEventData {
+    pub token: Address,
+    pub limit: i128,
+    pub period: u32,
+    pub threshold: u32,
+}
+
+// Event data for when the spend limit of a token is removed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpendLimitRemovedEventData {
+    pub token: Address,
 }
 
 // Event data for when the default threshold is changed.
@@ -63,3 +326,65 @@ pub struct PolicyRemovedEventData {
 pub struct DefaultThresholdChangedEventData {
     pub threshold: u32,
 }
+
+// Event data for when a clique is created.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CliqueCreatedEventData {
+    pub name: Symbol,
+    pub members: Vec<SignerKey>,
+    pub quorum: u32,
+}
+
+// Event data for when a clique is deleted.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CliqueDeletedEventData {
+    pub name: Symbol,
+}
+
+// Event data for when a member is added to a clique.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CliqueMemberAddedEventData {
+    pub name: Symbol,
+    pub member: SignerKey,
+}
+
+// Event data for when a member is removed from a clique.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CliqueMemberRemovedEventData {
+    pub name: Symbol,
+    pub member: SignerKey,
+}
+
+// Event data for when the quorum of a clique is changed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CliqueQuorumChangedEventData {
+    pub name: Symbol,
+    pub quorum: u32,
+}
+
+// Event data for when the cliques required by the default rule are changed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DefaultCliquesChangedEventData {
+    pub cliques: Vec<Symbol>,
+}
+
+// Event data for when the contract code is upgraded.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradedEventData {
+    pub wasm_hash: BytesN<32>,
+}
+
+// Event data for when the storage is migrated to a newer layout.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigratedEventData {
+    pub from: u32,
+    pub to: u32,
+}
@@ -1,32 +1,194 @@
 use crate::errors::MultiCliqueError;
-use crate::SignedMessage;
+use crate::{Clique, ConfigOp, Recovery, SessionKey, SessionScope, SignedMessage, SpendLimit};
+use commons::types::{Signer, SignerKey};
 use soroban_sdk::auth::Context;
-use soroban_sdk::{Address, BytesN, Env, Vec};
+use soroban_sdk::{Address, BytesN, Env, Map, Symbol, Vec};
 
 /// The `MultiCliqueTrait` defines the core functionalities of the MultiClique protocol.
-/// It offers methods for managing signers, setting default thresholds, and working with policies.
+/// It offers methods for managing signers and cliques, setting default thresholds, and working with policies.
 pub trait MultiCliqueTrait {
     /// Initializes the MultiClique contract with a set of signers and a default threshold.
     ///
     /// # Parameters
     /// - `env`: The execution environment.
-    /// - `signers`: A vector of signers' public keys.
-    /// - `default_threshold`: The default threshold required for authorization.
-    fn init(env: Env, signers: Vec<BytesN<32>>, default_threshold: u32);
+    /// - `signers`: A vector of signers' keys and their weights.
+    /// - `default_threshold`: The default signing weight required for authorization.
+    fn init(env: Env, signers: Vec<Signer>, default_threshold: u32);
 
     /// Adds a signer to the MultiClique contract.
     ///
     /// # Parameters
     /// - `env`: The execution environment.
-    /// - `signer`: The public key of the signer to add.
-    fn add_signer(env: Env, signer: BytesN<32>);
+    /// - `signer`: The key, weight and expiry ledger of the signer to add; an expiry of 0 never expires.
+    fn add_signer(env: Env, signer: Signer);
 
     /// Removes a signer from the MultiClique contract.
     ///
     /// # Parameters
     /// - `env`: The execution environment.
-    /// - `signer`: The public key of the signer to remove.
-    fn remove_signer(env: Env, signer: BytesN<32>);
+    /// - `signer`: The key of the signer to remove.
+    fn remove_signer(env: Env, signer: SignerKey);
+
+    /// Replaces a signer with another one, e.g. to rotate a compromised key.
+    /// The new signer takes over the clique memberships of the old one.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `old`: The key of the signer to replace.
+    /// - `new`: The signer to put in its place.
+    fn replace_signer(env: Env, old: SignerKey, new: Signer);
+
+    /// Applies a list of configuration changes atomically.
+    /// The signer limit, thresholds and clique quorums are only checked for the final configuration, so e.g. a
+    /// signer can be added before another one is removed at the limit. Every operation still has to apply on its own:
+    /// adding an existing signer, removing a missing one or exceeding the policy limit of a binding fails right away.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `ops`: The changes to apply, in order.
+    fn update_config(env: Env, ops: Vec<ConfigOp>);
+
+    /// Removes all expired signers along with their clique memberships. Can be called by anyone.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    fn prune_expired_signers(env: Env);
+
+    /// Adds a session key that may authorize calls to a limited set of functions on its own.
+    /// Session keys can never authorize calls to the MultiClique itself. Their calls still run through the policies
+    /// of the called contract, without signing weight, and have to stay within a native spend limit.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `public_key`: The ed25519 public key of the session.
+    /// - `scope`: The functions the session key may call.
+    /// - `expires_at`: The ledger sequence from which on the session key is expired.
+    /// - `budget`: The number of authorizations the session key may give, or `None` for no limit.
+    fn add_session_key(
+        env: Env,
+        public_key: BytesN<32>,
+        scope: Vec<SessionScope>,
+        expires_at: u32,
+        budget: Option<u32>,
+    );
+
+    /// Removes a session key.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `public_key`: The public key of the session to remove.
+    fn remove_session_key(env: Env, public_key: BytesN<32>);
+
+    /// Retrieves the public keys of all sessions.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// A vector of session public keys.
+    fn get_session_keys(env: Env) -> Vec<BytesN<32>>;
+
+    /// Retrieves the scope and expiry of a session key.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `public_key`: The public key of the session.
+    ///
+    /// # Returns
+    /// The session key, if it exists.
+    fn get_session_key(env: Env, public_key: BytesN<32>) -> Option<SessionKey>;
+
+    /// Retrieves the remaining call budget of a session key.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `public_key`: The public key of the session.
+    ///
+    /// # Returns
+    /// The number of authorizations left, or `None` if the session key has no budget.
+    fn get_session_budget(env: Env, public_key: BytesN<32>) -> Option<u32>;
+
+    /// Freezes the MultiClique in an emergency; a single signer is enough to authorize this.
+    /// While frozen, only unfreezing, rotating signers and cancelling a recovery can be authorized,
+    /// meeting the unfreeze threshold and the default cliques.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `ledgers`: The number of ledgers after which the MultiClique unfreezes automatically, or `None` to stay frozen.
+    fn freeze(env: Env, ledgers: Option<u32>);
+
+    /// Unfreezes the MultiClique.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    fn unfreeze(env: Env);
+
+    /// Checks whether the MultiClique is frozen.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// True if the MultiClique is frozen.
+    fn is_frozen(env: Env) -> bool;
+
+    /// Sets the threshold required to unfreeze or rotate signers while the MultiClique is frozen.
+    /// It can't be lower than the governance threshold.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `threshold`: The new unfreeze threshold.
+    fn set_unfreeze_threshold(env: Env, threshold: u32);
+
+    /// Retrieves the threshold required to unfreeze or rotate signers while the MultiClique is frozen.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The unfreeze threshold, or the governance threshold if it is higher. Without an unfreeze threshold, one more
+    /// than the governance threshold, as long as the signers that never expire can reach it.
+    fn get_unfreeze_threshold(env: Env) -> u32;
+
+    /// Sets a built-in spend limit for a token, replacing any previous one and resetting the spent amount.
+    /// Token transfers and approvals from the MultiClique are authorized with the lower threshold as long as the
+    /// amount spent within the current period stays within the limit; calls exceeding it need the default threshold.
+    /// Spend limits only apply to tokens without an attached policy.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `token`: The address of the token contract.
+    /// - `limit`: The amount that can be spent per period with the lower threshold.
+    /// - `period`: The length of a period in ledgers.
+    /// - `threshold`: The threshold within the limit; it can't exceed the default threshold.
+    fn set_spend_limit(env: Env, token: Address, limit: i128, period: u32, threshold: u32);
+
+    /// Removes the spend limit of a token.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `token`: The address of the token contract.
+    fn remove_spend_limit(env: Env, token: Address);
+
+    /// Retrieves the spend limit of a token.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `token`: The address of the token contract.
+    ///
+    /// # Returns
+    /// The spend limit, if any.
+    fn get_spend_limit(env: Env, token: Address) -> Option<SpendLimit>;
+
+    /// Retrieves the amount spent with the lower threshold within the current period.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `token`: The address of the token contract.
+    ///
+    /// # Returns
+    /// The amount spent in the current period.
+    fn get_spent(env: Env, token: Address) -> i128;
 
     /// Retrieves the signers associated with the MultiClique contract.
     ///
@@ -34,14 +196,14 @@ pub trait MultiCliqueTrait {
     /// - `env`: The execution environment.
     ///
     /// # Returns
-    /// A vector of signers' public keys.
-    fn get_signers(env: Env) -> Vec<BytesN<32>>;
+    /// A vector of signers' keys, their weights and expiry ledgers, including expired signers that have not been pruned yet.
+    fn get_signers(env: Env) -> Vec<Signer>;
 
     /// Sets the default threshold for the MultiClique contract.
     ///
     /// # Parameters
     /// - `env`: The execution environment.
-    /// - `threshold`: The default threshold to set.
+    /// - `threshold`: The default threshold to set, as a summed signing weight.
     fn set_default_threshold(env: Env, threshold: u32);
 
     /// Retrieves the default threshold of the MultiClique contract.
@@ -53,37 +215,367 @@ pub trait MultiCliqueTrait {
     /// The default threshold value.
     fn get_default_threshold(env: Env) -> u32;
 
+    /// Sets the guardians that can recover the MultiClique if too many signer keys are lost.
+    /// A recovery initiated by the previous guardians is cancelled.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `guardians`: The addresses of the guardians, or an empty vector to disable recovery.
+    /// - `threshold`: The number of guardians required to initiate a recovery.
+    /// - `delay`: The number of ledgers the signers have to cancel a recovery before it can be executed, at least
+    ///   `MIN_RECOVERY_DELAY`.
+    fn set_guardians(env: Env, guardians: Vec<Address>, threshold: u32, delay: u32);
+
+    /// Retrieves the guardians of the MultiClique.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// A vector of guardian addresses.
+    fn get_guardians(env: Env) -> Vec<Address>;
+
+    /// Retrieves the number of guardians required to initiate a recovery.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The guardian threshold.
+    fn get_guardian_threshold(env: Env) -> u32;
+
+    /// Retrieves the number of ledgers between initiating and executing a recovery.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The recovery delay in ledgers.
+    fn get_recovery_delay(env: Env) -> u32;
+
+    /// Initiates the replacement of the signer set; every given guardian has to authorize the call.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `guardians`: The guardians approving the recovery.
+    /// - `signers`: The new signers.
+    /// - `threshold`: The new default threshold.
+    fn initiate_recovery(env: Env, guardians: Vec<Address>, signers: Vec<Signer>, threshold: u32);
+
+    /// Cancels a pending recovery.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    fn cancel_recovery(env: Env);
+
+    /// Executes a pending recovery once its delay has passed. Can be called by anyone.
+    /// Cliques, session keys as well as the governance, deploy and unfreeze thresholds are reset, as they refer to
+    /// the old signers, and the MultiClique is unfrozen.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    fn execute_recovery(env: Env);
+
+    /// Retrieves the pending recovery.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The pending recovery, if any.
+    fn get_recovery(env: Env) -> Option<Recovery>;
+
+    /// Sets the governance threshold required for changes to the MultiClique's own configuration.
+    /// Once set, it replaces the default threshold for calls to the MultiClique itself, so it can't be 0 or lower
+    /// than the default threshold.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `threshold`: The new governance threshold.
+    fn set_gov_threshold(env: Env, threshold: u32);
+
+    /// Retrieves the governance threshold required for changes to the MultiClique's own configuration.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The governance threshold, or the default threshold if none has been set.
+    fn get_gov_threshold(env: Env) -> u32;
+
+    /// Sets the threshold required to deploy contracts on behalf of the MultiClique.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `threshold`: The new deploy threshold.
+    fn set_deploy_threshold(env: Env, threshold: u32);
+
+    /// Retrieves the threshold required to deploy contracts on behalf of the MultiClique.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The deploy threshold, or the default threshold if none has been set.
+    fn get_deploy_threshold(env: Env) -> u32;
+
+    /// Sets the policy run for every contract deployment, replacing any previous one.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `policy`: The address of a contract implementing `MultiCliqueDeployPolicyTrait`.
+    fn set_deploy_policy(env: Env, policy: Address);
+
+    /// Removes the deploy policy, so deployments only have to meet the deploy threshold.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    fn remove_deploy_policy(env: Env);
+
+    /// Retrieves the deploy policy.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The address of the deploy policy, if any.
+    fn get_deploy_policy(env: Env) -> Option<Address>;
+
+    /// Creates a named clique, a group of signers that has to reach its own quorum.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `name`: The name of the clique.
+    /// - `members`: The keys of the clique members; each has to be a signer.
+    /// - `quorum`: The number of members that have to sign if the clique is required, at most the number of members that never expire.
+    fn create_clique(env: Env, name: Symbol, members: Vec<SignerKey>, quorum: u32);
+
+    /// Deletes a clique. Cliques required by the default rule cannot be deleted.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `name`: The name of the clique to delete.
+    fn delete_clique(env: Env, name: Symbol);
+
+    /// Adds a signer to a clique.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `name`: The name of the clique.
+    /// - `member`: The key of the signer to add.
+    fn add_clique_member(env: Env, name: Symbol, member: SignerKey);
+
+    /// Removes a signer from a clique.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `name`: The name of the clique.
+    /// - `member`: The key of the signer to remove.
+    fn remove_clique_member(env: Env, name: Symbol, member: SignerKey);
+
+    /// Sets the quorum of a clique.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `name`: The name of the clique.
+    /// - `quorum`: The number of members that have to sign.
+    fn set_clique_quorum(env: Env, name: Symbol, quorum: u32);
+
+    /// Retrieves all cliques of the MultiClique contract.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// A map of clique names to their members and quorum.
+    fn get_cliques(env: Env) -> Map<Symbol, Clique>;
+
+    /// Sets the cliques whose quorum is required in addition to the default threshold, also for deployments and while
+    /// the MultiClique is frozen.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `cliques`: The names of the required cliques.
+    fn set_default_cliques(env: Env, cliques: Vec<Symbol>);
+
+    /// Retrieves the cliques required in addition to the default threshold.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// A vector of clique names.
+    fn get_default_cliques(env: Env) -> Vec<Symbol>;
+
     /// Attaches a policy to a specific context within the MultiClique contract.
+    /// The policy is put on top of the policies already attached to the context.
+    ///
+    /// A policy bound to a function of a context takes precedence over the policies bound to the
+    /// context as a whole, which in turn take precedence over the fallback policy and the default threshold.
     ///
     /// # Parameters
     /// - `env`: The execution environment.
     /// - `policy`: The address of the policy to attach.
     /// - `context`: The context addresses to which the policy should be attached.
-    fn attach_policy(env: Env, policy: Address, context: Vec<Address>);
+    /// - `fn_names`: The functions of the contexts to bind the policy to, or empty to bind it to the contexts as a whole.
+    fn attach_policy(env: Env, policy: Address, context: Vec<Address>, fn_names: Vec<Symbol>);
+
+    /// Detaches all policies from a specific context within the MultiClique contract.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `context`: The context addresses from which the policies should be detached.
+    /// - `fn_names`: The functions of the contexts to detach the policies from, or empty for the contract-wide policies.
+    fn detach_policy(env: Env, context: Vec<Address>, fn_names: Vec<Symbol>);
 
-    /// Detaches a policy from a specific context within the MultiClique contract.
+    /// Removes a single policy from the policies attached to a specific context.
     ///
     /// # Parameters
     /// - `env`: The execution environment.
-    /// - `context`: The context addresses from which the policy should be detached.
-    fn detach_policy(env: Env, context: Vec<Address>);
+    /// - `policy`: The address of the policy to remove.
+    /// - `context`: The context addresses from which the policy should be removed.
+    /// - `fn_names`: The functions of the contexts to remove the policy from, or empty for the contract-wide policies.
+    fn remove_policy(env: Env, policy: Address, context: Vec<Address>, fn_names: Vec<Symbol>);
+
+    /// Changes the order in which the policies attached to a context are run.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `context`: The context address whose policies should be reordered.
+    /// - `fn_name`: The function of the context, or `None` for the contract-wide policies.
+    /// - `policies`: The attached policies in their new order.
+    fn reorder_policies(
+        env: Env,
+        context: Address,
+        fn_name: Option<Symbol>,
+        policies: Vec<Address>,
+    );
 
     /// Retrieves the policies associated with specific contexts within the MultiClique contract.
     ///
     /// # Parameters
     /// - `env`: The execution environment.
     /// - `context`: The context addresses to query.
+    /// - `fn_names`: The functions of the contexts to query, or empty for the contract-wide policies.
+    ///
+    /// # Returns
+    /// A vector of all policy addresses attached to any of the queried bindings.
+    fn get_policies(env: Env, context: Vec<Address>, fn_names: Vec<Symbol>) -> Vec<Address>;
+
+    /// Sets the fallback policy, replacing any previous one.
+    /// It runs for every contract context without a policy bound to the contract or the called function.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `policy`: The address of the fallback policy.
+    fn set_fallback_policy(env: Env, policy: Address);
+
+    /// Removes the fallback policy, so contract contexts without a binding use the default threshold again.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    fn remove_fallback_policy(env: Env);
+
+    /// Retrieves the fallback policy.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The address of the fallback policy, if any.
+    fn get_fallback_policy(env: Env) -> Option<Address>;
+
+    /// Enables or disables strict mode.
+    /// In strict mode calls to contracts without a policy for the call are rejected unless the contract is on the allowlist.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `enabled`: Whether strict mode should be enabled.
+    fn set_strict_mode(env: Env, enabled: bool);
+
+    /// Checks whether strict mode is enabled.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// True if strict mode is enabled.
+    fn is_strict_mode(env: Env) -> bool;
+
+    /// Adds contracts to the allowlist used in strict mode.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `contracts`: The contract addresses to allow.
+    fn allow_contracts(env: Env, contracts: Vec<Address>);
+
+    /// Removes contracts from the allowlist used in strict mode.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `contracts`: The contract addresses to disallow.
+    fn disallow_contracts(env: Env, contracts: Vec<Address>);
+
+    /// Retrieves the allowlist used in strict mode.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
     ///
     /// # Returns
-    /// A vector of policy addresses.
-    fn get_policies(env: Env, context: Vec<Address>) -> Vec<Address>;
+    /// A vector of allowed contract addresses.
+    fn get_allowlist(env: Env) -> Vec<Address>;
+
+    /// Retrieves the ordered stack of policies attached to a single context.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `context`: The context address to query.
+    /// - `fn_name`: The function of the context, or `None` for the contract-wide policies.
+    ///
+    /// # Returns
+    /// A vector of policy addresses in the order they are run.
+    fn get_policy_stack(env: Env, context: Address, fn_name: Option<Symbol>) -> Vec<Address>;
+
+    /// Extends the lifetime of the contract's storage, so an idle MultiClique does not get archived.
+    /// This is permissionless, as it happens implicitly on every state-changing call anyway.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    fn extend_ttl(env: Env);
+
+    /// Upgrades the contract to new wasm code. Requires the contract's own authorization, so the
+    /// upgrade is subject to the default threshold or the policy attached to the contract itself.
+    ///
+    /// The new code takes effect after the current invocation; call `migrate` afterwards to move
+    /// the storage to the layout of the new code.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    /// - `new_wasm_hash`: The hash of the installed wasm code to upgrade to.
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>);
+
+    /// Retrieves the storage layout version of the contract.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    ///
+    /// # Returns
+    /// The stored version; contracts deployed before versioning report version 1.
+    fn version(env: Env) -> u32;
+
+    /// Rewrites storage written by older versions of the contract to the current layout.
+    /// This is permissionless and does nothing if the storage is already up to date.
+    ///
+    /// # Parameters
+    /// - `env`: The execution environment.
+    fn migrate(env: Env);
 
     /// Internal method for checking the authorization of a transaction within the MultiClique contract.
     ///
     /// # Parameters
     /// - `env`: The execution environment.
     /// - `signature_payload`: The payload to be signed.
-    /// - `signatures`: The vector of signed messages.
+    /// - `signatures`: The vector of signed messages, strictly sorted by signer key.
     /// - `auth_context`: The authorization context information.
     ///
     /// # Returns
@@ -1,47 +1,212 @@
 #![no_std]
 
-use commons::traits::MultiCliquePolicyTrait;
-use core::cmp::min;
-use soroban_sdk::auth::Context;
+use commons::traits::{MultiCliqueDeployPolicyTrait, MultiCliquePolicyTrait};
+use commons::types::{Signer, SignerKey};
+use soroban_sdk::auth::{
+    Context, ContractContext, ContractExecutable, CreateContractHostFnContext,
+};
 use soroban_sdk::{
-    contract, contractimpl, contracttype, panic_with_error, Address, BytesN, Env, Symbol, Val, Vec,
+    contract, contractimpl, contracttype, panic_with_error, vec, Address, Bytes, BytesN, Env, Map,
+    Symbol, TryFromVal, Val, Vec,
 };
 
 mod errors;
 mod events;
 pub mod interface;
+#[cfg(any(test, feature = "passkeys"))]
+mod webauthn;
 
 #[cfg(test)]
 mod test;
 
 use crate::errors::MultiCliqueError;
 use crate::events::{
-    DefaultThresholdChangedEventData, InitEventData, PolicyAddedEventData, PolicyRemovedEventData,
-    SignerAddedEventData, SignerRemovedEventData, ADDED, CHANGED, GOV, INIT, POLICY, REMOVED,
-    SIGNER,
+    AllowlistAddedEventData, AllowlistRemovedEventData, CliqueCreatedEventData,
+    CliqueDeletedEventData, CliqueMemberAddedEventData, CliqueMemberRemovedEventData,
+    CliqueQuorumChangedEventData, ConfigUpdatedEventData, DefaultCliquesChangedEventData,
+    DefaultThresholdChangedEventData, DeployPolicyAddedEventData, DeployPolicyRemovedEventData,
+    DeployThresholdChangedEventData, FallbackPolicyChangedEventData,
+    FallbackPolicyRemovedEventData, FrozenEventData, GovThresholdChangedEventData,
+    GuardiansChangedEventData, InitEventData, MigratedEventData, PolicyAddedEventData,
+    PolicyRemovedEventData, PolicyStackChangedEventData, PolicyStackRemovedEventData,
+    RecoveryCancelledEventData, RecoveryExecutedEventData, RecoveryInitiatedEventData,
+    SessionKeyAddedEventData, SessionKeyRemovedEventData, SignerAddedEventData,
+    SignerRemovedEventData, SignerReplacedEventData, SpendLimitChangedEventData,
+    SpendLimitRemovedEventData, StrictModeChangedEventData, UnfreezeThresholdChangedEventData,
+    UnfrozenEventData, UpgradedEventData, ADDED, ALLOW, CANCELLED, CHANGED, CLIQUE, DEFAULT,
+    DEPLOY, EXECUTED, FALLBACK, FROZEN, GOV, GUARDIAN, INIT, INITIATED, LIMIT, MEMBER, MIGRATED,
+    POLICY, RECOVERY, REMOVED, REPLACED, SESSION, SIGNER, STACK, STRICT, THRESHOLD, UNFROZEN,
+    UPDATED, UPGRADED,
 };
 use crate::interface::MultiCliqueTrait;
 
-/// Declares the SignedMessage structure, containing the public key and signature.
+/// An ed25519 signature over the signature payload.
 #[contracttype]
 #[derive(Clone)]
-pub struct SignedMessage {
+pub struct Ed25519Signature {
     pub public_key: BytesN<32>,
     pub signature: BytesN<64>,
 }
 
+/// A WebAuthn assertion of a secp256r1 passkey, with the signature payload as its challenge.
+#[contracttype]
+#[derive(Clone)]
+pub struct WebAuthnSignature {
+    pub public_key: BytesN<65>,
+    pub authenticator_data: Bytes,
+    pub client_data_json: Bytes,
+    pub signature: BytesN<64>,
+}
+
+/// Declares the SignedMessage enum, containing the signature of one signer in the format of its key.
+///
+/// Address signers do not carry a signature; their approval is proven by authorizing
+/// the signature payload on the address itself.
+///
+/// Session keys are no signers; their signature has to be the only one submitted.
+#[contracttype]
+#[derive(Clone)]
+pub enum SignedMessage {
+    Ed25519(Ed25519Signature),
+    Secp256r1(WebAuthnSignature),
+    Address(Address),
+    Session(Ed25519Signature),
+}
+
+impl SignedMessage {
+    /// Returns the key of the signer that created this message.
+    pub fn signer_key(&self) -> SignerKey {
+        match self {
+            SignedMessage::Ed25519(message) => SignerKey::Ed25519(message.public_key.clone()),
+            SignedMessage::Secp256r1(message) => SignerKey::Secp256r1(message.public_key.clone()),
+            SignedMessage::Address(address) => SignerKey::Address(address.clone()),
+            SignedMessage::Session(message) => SignerKey::Ed25519(message.public_key.clone()),
+        }
+    }
+}
+
+/// A function a session key may authorize calls to.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionScope {
+    pub contract: Address,
+    pub fn_name: Symbol,
+}
+
+/// A key registered by the MultiClique to authorize a limited set of calls on its own, e.g. for bots.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionKey {
+    pub scope: Vec<SessionScope>,
+    /// The ledger sequence from which on the session key is expired.
+    pub expires_at: u32,
+}
+
+/// The layout of a signer in versions 2 and 3, before signers could expire.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignerV2 {
+    pub key: SignerKey,
+    pub weight: u32,
+}
+
+/// A built-in spend limit for a token, see `set_spend_limit`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpendLimit {
+    /// The amount that can be spent per period with the lower threshold.
+    pub limit: i128,
+    /// The length of a period in ledgers.
+    pub period: u32,
+    /// The threshold for transfers and approvals within the limit.
+    pub threshold: u32,
+}
+
+/// The amount spent with the lower threshold of a spend limit in the current period.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Spent {
+    pub amount: i128,
+    /// The ledger the current period started at.
+    pub period_start: u32,
+}
+
+/// A pending replacement of the signer set, initiated by the guardians.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Recovery {
+    pub signers: Vec<Signer>,
+    pub threshold: u32,
+    /// The ledger from which on the recovery can be executed.
+    pub executable_at: u32,
+}
+
+/// A single change applied by `update_config`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConfigOp {
+    AddSigner(Signer),
+    RemoveSigner(SignerKey),
+    ReplaceSigner(SignerKey, Signer),
+    SetDefaultThreshold(u32),
+    AttachPolicy(Address, Vec<Address>, Vec<Symbol>),
+    DetachPolicy(Vec<Address>, Vec<Symbol>),
+}
+
+/// A named group of signers that has to reach its own quorum, e.g. "2 of the board".
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Clique {
+    pub members: Vec<SignerKey>,
+    pub quorum: u32,
+}
+
 /// Enum to represent different keys used in storage for the contract.
 #[contracttype]
 #[derive(Clone)]
 enum DataKey {
+    Version,
     DefaultThreshold,
+    DefaultCliques,
     Signers,
+    Guardians,
+    GuardianThreshold,
+    RecoveryDelay,
+    Recovery,
+    Cliques,
     SpendLimit(Address),
+    Spent(Address),
     Policy(Address),
+    Policies(Address),
+    FnPolicies(Address, Symbol),
+    FallbackPolicy,
+    StrictMode,
+    Allowlist,
+    DeployThreshold,
+    DeployPolicy,
+    GovThreshold,
+    SessionKeys,
+    SessionKey(BytesN<32>),
+    SessionBudget(BytesN<32>),
+    Frozen,
+    FrozenUntil,
+    UnfreezeThreshold,
 }
 
 pub const BUMP_A_YEAR: u32 = 6312000;
+pub const BUMP_THRESHOLD: u32 = BUMP_A_YEAR / 2;
 pub const THRESHOLD_LIMIT: u32 = 16;
+pub const POLICY_LIMIT: u32 = 8;
+/// The shortest recovery delay, about a day at five seconds per ledger.
+pub const MIN_RECOVERY_DELAY: u32 = 17280;
+
+/// The storage layout version of this code, see `migrate`.
+///
+/// - 1: signers stored as plain ed25519 public keys without weights (no version stored)
+/// - 2: signers stored as weighted `Signer`s with typed keys
+/// - 3: policies stored as ordered stacks per context or function instead of a single policy
+/// - 4: signers carry an expiry ledger
+pub const VERSION: u32 = 4;
 
 #[contract]
 pub struct Contract;
@@ -49,24 +214,19 @@ pub struct Contract;
 /// see `MultiCliqueTrait` for documentation
 #[contractimpl]
 impl MultiCliqueTrait for Contract {
-    fn init(env: Env, signers: Vec<BytesN<32>>, default_threshold: u32) {
+    fn init(env: Env, signers: Vec<Signer>, default_threshold: u32) {
         if env.storage().instance().has(&DataKey::Signers) {
             panic_with_error!(&env, MultiCliqueError::AlreadyInitialized);
         }
 
-        if signers.len() > THRESHOLD_LIMIT {
-            panic_with_error!(&env, MultiCliqueError::SignerLimitExceeded);
-        }
-
-        let valid_thresholds = 0..signers.len() + 1;
-        if !valid_thresholds.contains(&default_threshold) {
-            panic_with_error!(&env, MultiCliqueError::InvalidThreshold);
-        }
+        validate_signers(&env, &signers, default_threshold);
 
+        env.storage().instance().set(&DataKey::Version, &VERSION);
         env.storage().instance().set(&DataKey::Signers, &signers);
         env.storage()
             .instance()
             .set(&DataKey::DefaultThreshold, &default_threshold);
+        bump_instance(&env);
 
         env.events().publish(
             (GOV, INIT),
@@ -77,189 +237,1063 @@ impl MultiCliqueTrait for Contract {
         );
     }
 
-    fn add_signer(env: Env, signer: BytesN<32>) {
+    fn add_signer(env: Env, signer: Signer) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        add_signer(&env, signer);
+        validate_config(&env);
+    }
+
+    fn remove_signer(env: Env, signer: SignerKey) {
         env.current_contract_address().require_auth();
-        let mut signers: Vec<BytesN<32>> = env.storage().instance().get(&DataKey::Signers).unwrap();
+        bump_instance(&env);
+        remove_signer(&env, signer);
+        validate_config(&env);
+    }
 
-        if signers.contains(&signer) {
-            panic_with_error!(&env, MultiCliqueError::SignerAlreadyAdded);
+    fn replace_signer(env: Env, old: SignerKey, new: Signer) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        replace_signer(&env, old, new);
+        validate_config(&env);
+    }
+
+    fn update_config(env: Env, ops: Vec<ConfigOp>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        for op in ops.iter() {
+            match op {
+                ConfigOp::AddSigner(signer) => add_signer(&env, signer),
+                ConfigOp::RemoveSigner(signer) => remove_signer(&env, signer),
+                ConfigOp::ReplaceSigner(old, new) => replace_signer(&env, old, new),
+                ConfigOp::SetDefaultThreshold(threshold) => set_default_threshold(&env, threshold),
+                ConfigOp::AttachPolicy(policy, context, fn_names) => {
+                    attach_policy(&env, policy, context, fn_names)
+                }
+                ConfigOp::DetachPolicy(context, fn_names) => detach_policy(&env, context, fn_names),
+            }
         }
+        validate_config(&env);
+
+        let signers = Self::get_signers(env.clone());
+        env.events().publish(
+            (GOV, UPDATED),
+            ConfigUpdatedEventData {
+                operations: ops.len(),
+                signers: signers.len(),
+                threshold: Self::get_default_threshold(env.clone()),
+            },
+        );
+    }
 
-        if signers.len() == THRESHOLD_LIMIT {
-            panic_with_error!(&env, MultiCliqueError::SignerLimitExceeded);
+    fn prune_expired_signers(env: Env) {
+        bump_instance(&env);
+        for signer in Self::get_signers(env.clone()).iter() {
+            if !is_live(&env, &signer) {
+                remove_signer(&env, signer.key);
+            }
         }
+        validate_config(&env);
+    }
 
-        signers.push_back(signer.clone());
-        env.storage().instance().set(&DataKey::Signers, &signers);
-        env.events()
-            .publish((SIGNER, ADDED), SignerAddedEventData { signer });
+    fn add_session_key(
+        env: Env,
+        public_key: BytesN<32>,
+        scope: Vec<SessionScope>,
+        expires_at: u32,
+        budget: Option<u32>,
+    ) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+
+        let mut keys = Self::get_session_keys(env.clone());
+        if keys.contains(&public_key) {
+            panic_with_error!(&env, MultiCliqueError::SessionKeyExists);
+        }
+        if expires_at <= env.ledger().sequence() {
+            panic_with_error!(&env, MultiCliqueError::InvalidSignerExpiry);
+        }
+        for entry in scope.iter() {
+            if entry.contract == env.current_contract_address() {
+                panic_with_error!(&env, MultiCliqueError::SessionKeyOutOfScope);
+            }
+        }
+
+        let session = SessionKey { scope, expires_at };
+        keys.push_back(public_key.clone());
+        env.storage().instance().set(&DataKey::SessionKeys, &keys);
+        env.storage()
+            .instance()
+            .set(&DataKey::SessionKey(public_key.clone()), &session);
+        if let Some(budget) = budget {
+            env.storage()
+                .instance()
+                .set(&DataKey::SessionBudget(public_key.clone()), &budget);
+        }
+        env.events().publish(
+            (SESSION, ADDED),
+            SessionKeyAddedEventData {
+                public_key,
+                scope: session.scope,
+                expires_at,
+                budget: budget.unwrap_or(u32::MAX),
+            },
+        );
+    }
+
+    fn remove_session_key(env: Env, public_key: BytesN<32>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+
+        let mut keys = Self::get_session_keys(env.clone());
+        match keys.first_index_of(&public_key) {
+            None => panic_with_error!(&env, MultiCliqueError::SessionKeyDoesNotExist),
+            Some(index) => keys.remove(index),
+        };
+        env.storage().instance().set(&DataKey::SessionKeys, &keys);
+        env.storage()
+            .instance()
+            .remove(&DataKey::SessionKey(public_key.clone()));
+        env.storage()
+            .instance()
+            .remove(&DataKey::SessionBudget(public_key.clone()));
+        env.events().publish(
+            (SESSION, REMOVED),
+            SessionKeyRemovedEventData { public_key },
+        );
+    }
+
+    fn get_session_keys(env: Env) -> Vec<BytesN<32>> {
+        env.storage()
+            .instance()
+            .get(&DataKey::SessionKeys)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    fn get_session_key(env: Env, public_key: BytesN<32>) -> Option<SessionKey> {
+        env.storage()
+            .instance()
+            .get(&DataKey::SessionKey(public_key))
+    }
+
+    fn get_session_budget(env: Env, public_key: BytesN<32>) -> Option<u32> {
+        env.storage()
+            .instance()
+            .get(&DataKey::SessionBudget(public_key))
+    }
+
+    fn freeze(env: Env, ledgers: Option<u32>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let until = ledgers.map(|ledgers| env.ledger().sequence().saturating_add(ledgers));
+        env.storage().instance().set(&DataKey::Frozen, &true);
+        match until {
+            Some(until) => env.storage().instance().set(&DataKey::FrozenUntil, &until),
+            None => env.storage().instance().remove(&DataKey::FrozenUntil),
+        }
+        env.events().publish(
+            (GOV, FROZEN),
+            FrozenEventData {
+                until: until.unwrap_or(0),
+            },
+        );
     }
 
-    fn remove_signer(env: Env, signer: BytesN<32>) {
+    fn unfreeze(env: Env) {
         env.current_contract_address().require_auth();
-        let mut signers: Vec<BytesN<32>> = env.storage().instance().get(&DataKey::Signers).unwrap();
-        let threshold = env
+        bump_instance(&env);
+        env.storage().instance().remove(&DataKey::Frozen);
+        env.storage().instance().remove(&DataKey::FrozenUntil);
+        env.events().publish(
+            (GOV, UNFROZEN),
+            UnfrozenEventData {
+                ledger: env.ledger().sequence(),
+            },
+        );
+    }
+
+    fn is_frozen(env: Env) -> bool {
+        let frozen = env
             .storage()
             .instance()
-            .get(&DataKey::DefaultThreshold)
-            .unwrap_or(0);
+            .get(&DataKey::Frozen)
+            .unwrap_or(false);
+        match env
+            .storage()
+            .instance()
+            .get::<DataKey, u32>(&DataKey::FrozenUntil)
+        {
+            Some(until) => frozen && env.ledger().sequence() < until,
+            None => frozen,
+        }
+    }
+
+    fn set_unfreeze_threshold(env: Env, threshold: u32) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
 
-        if signers.len() == threshold {
+        // unfreezing must never be cheaper than governing the multiclique
+        if threshold < Self::get_gov_threshold(env.clone()) {
             panic_with_error!(&env, MultiCliqueError::InvalidThreshold);
         }
 
-        match signers.first_index_of(&signer) {
-            None => panic_with_error!(&env, MultiCliqueError::SignerDoesNotExist),
-            Some(index) => signers.remove(index),
+        env.storage()
+            .instance()
+            .set(&DataKey::UnfreezeThreshold, &threshold);
+        validate_config(&env);
+        env.events().publish(
+            (FROZEN, THRESHOLD),
+            UnfreezeThresholdChangedEventData { threshold },
+        );
+    }
+
+    fn get_unfreeze_threshold(env: Env) -> u32 {
+        let gov_threshold = Self::get_gov_threshold(env.clone());
+        match env.storage().instance().get(&DataKey::UnfreezeThreshold) {
+            Some(threshold) => gov_threshold.max(threshold),
+            // higher than governing the multiclique, as far as the permanent signers can reach it
+            None => {
+                let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+                gov_threshold
+                    .saturating_add(1)
+                    .min(permanent_weight(&signers))
+                    .max(gov_threshold)
+            }
+        }
+    }
+
+    fn set_spend_limit(env: Env, token: Address, limit: i128, period: u32, threshold: u32) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+
+        if limit < 0 || period == 0 {
+            panic_with_error!(&env, MultiCliqueError::InvalidSpendLimit);
+        }
+        if threshold > Self::get_default_threshold(env.clone()) {
+            panic_with_error!(&env, MultiCliqueError::InvalidThreshold);
+        }
+
+        let spend_limit = SpendLimit {
+            limit,
+            period,
+            threshold,
         };
+        env.storage()
+            .instance()
+            .set(&DataKey::SpendLimit(token.clone()), &spend_limit);
+        env.storage()
+            .instance()
+            .remove(&DataKey::Spent(token.clone()));
+        env.events().publish(
+            (LIMIT, CHANGED),
+            SpendLimitChangedEventData {
+                token,
+                limit,
+                period,
+                threshold,
+            },
+        );
+    }
 
-        env.storage().instance().set(&DataKey::Signers, &signers);
+    fn remove_spend_limit(env: Env, token: Address) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+
+        if !env
+            .storage()
+            .instance()
+            .has(&DataKey::SpendLimit(token.clone()))
+        {
+            panic_with_error!(&env, MultiCliqueError::SpendLimitDoesNotExist);
+        }
+        env.storage()
+            .instance()
+            .remove(&DataKey::SpendLimit(token.clone()));
+        env.storage()
+            .instance()
+            .remove(&DataKey::Spent(token.clone()));
         env.events()
-            .publish((SIGNER, REMOVED), SignerRemovedEventData { signer });
+            .publish((LIMIT, REMOVED), SpendLimitRemovedEventData { token });
+    }
+
+    fn get_spend_limit(env: Env, token: Address) -> Option<SpendLimit> {
+        env.storage().instance().get(&DataKey::SpendLimit(token))
+    }
+
+    fn get_spent(env: Env, token: Address) -> i128 {
+        match Self::get_spend_limit(env.clone(), token.clone()) {
+            Some(spend_limit) => current_spent(&env, &token, &spend_limit).amount,
+            None => 0,
+        }
     }
 
-    fn get_signers(env: Env) -> Vec<BytesN<32>> {
+    fn get_signers(env: Env) -> Vec<Signer> {
         env.storage().instance().get(&DataKey::Signers).unwrap()
     }
 
     fn set_default_threshold(env: Env, threshold: u32) {
         env.current_contract_address().require_auth();
+        bump_instance(&env);
+        set_default_threshold(&env, threshold);
+        validate_config(&env);
+    }
+
+    fn get_default_threshold(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::DefaultThreshold)
+            .unwrap_or(0)
+    }
 
-        let signers: Vec<BytesN<32>> = env.storage().instance().get(&DataKey::Signers).unwrap();
-        let valid_thresholds = 0..min(signers.len() + 1, THRESHOLD_LIMIT + 1);
+    fn set_guardians(env: Env, guardians: Vec<Address>, threshold: u32, delay: u32) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
 
-        if !valid_thresholds.contains(&threshold) {
+        for i in 0..guardians.len() {
+            if guardians.first_index_of(guardians.get_unchecked(i)) != Some(i) {
+                panic_with_error!(&env, MultiCliqueError::DuplicateGuardian);
+            }
+        }
+        if threshold > guardians.len() || (threshold == 0 && !guardians.is_empty()) {
             panic_with_error!(&env, MultiCliqueError::InvalidThreshold);
         }
+        // the signers need time to notice and cancel a recovery
+        if delay < MIN_RECOVERY_DELAY && !guardians.is_empty() {
+            panic_with_error!(&env, MultiCliqueError::InvalidRecoveryDelay);
+        }
 
+        // a pending recovery was initiated by the previous guardians
+        if let Some(recovery) = Self::get_recovery(env.clone()) {
+            env.storage().instance().remove(&DataKey::Recovery);
+            env.events().publish(
+                (RECOVERY, CANCELLED),
+                RecoveryCancelledEventData {
+                    signers: recovery.signers,
+                    threshold: recovery.threshold,
+                },
+            );
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Guardians, &guardians);
+        env.storage()
+            .instance()
+            .set(&DataKey::GuardianThreshold, &threshold);
         env.storage()
             .instance()
-            .set(&DataKey::DefaultThreshold, &threshold);
+            .set(&DataKey::RecoveryDelay, &delay);
         env.events().publish(
-            (GOV, CHANGED),
-            DefaultThresholdChangedEventData { threshold },
+            (GUARDIAN, CHANGED),
+            GuardiansChangedEventData {
+                guardians,
+                threshold,
+                delay,
+            },
         );
     }
 
-    fn get_default_threshold(env: Env) -> u32 {
+    fn get_guardians(env: Env) -> Vec<Address> {
         env.storage()
             .instance()
-            .get(&DataKey::DefaultThreshold)
+            .get(&DataKey::Guardians)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    fn get_guardian_threshold(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::GuardianThreshold)
             .unwrap_or(0)
     }
 
-    fn attach_policy(env: Env, policy: Address, context: Vec<Address>) {
-        env.current_contract_address().require_auth();
-        for ctx in context.iter() {
-            if env.storage().instance().has(&DataKey::Policy(ctx.clone())) {
-                panic_with_error!(&env, MultiCliqueError::ContractPolicyExists);
+    fn get_recovery_delay(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::RecoveryDelay)
+            .unwrap_or(0)
+    }
+
+    fn initiate_recovery(env: Env, guardians: Vec<Address>, signers: Vec<Signer>, threshold: u32) {
+        bump_instance(&env);
+        if env.storage().instance().has(&DataKey::Recovery) {
+            panic_with_error!(&env, MultiCliqueError::RecoveryPending);
+        }
+
+        let known = Self::get_guardians(env.clone());
+        for i in 0..guardians.len() {
+            let guardian = guardians.get_unchecked(i);
+            if !known.contains(&guardian) {
+                panic_with_error!(&env, MultiCliqueError::NotAGuardian);
             }
-            env.storage().instance().set(&DataKey::Policy(ctx), &policy);
+            if guardians.first_index_of(&guardian) != Some(i) {
+                panic_with_error!(&env, MultiCliqueError::DuplicateGuardian);
+            }
+            guardian.require_auth();
         }
-        env.events()
-            .publish((POLICY, ADDED), PolicyAddedEventData { policy, context });
+        // without guardians the threshold is 0, which must not allow anyone to recover
+        if known.is_empty() || guardians.len() < Self::get_guardian_threshold(env.clone()) {
+            panic_with_error!(&env, MultiCliqueError::GuardianThresholdNotMet);
+        }
+
+        validate_signers(&env, &signers, threshold);
+
+        let recovery = Recovery {
+            signers,
+            threshold,
+            executable_at: env
+                .ledger()
+                .sequence()
+                .saturating_add(Self::get_recovery_delay(env.clone())),
+        };
+        env.storage().instance().set(&DataKey::Recovery, &recovery);
+        env.events().publish(
+            (RECOVERY, INITIATED),
+            RecoveryInitiatedEventData {
+                guardians,
+                signers: recovery.signers,
+                threshold: recovery.threshold,
+                executable_at: recovery.executable_at,
+            },
+        );
     }
 
-    fn detach_policy(env: Env, context: Vec<Address>) {
+    fn cancel_recovery(env: Env) {
         env.current_contract_address().require_auth();
-        for ctx in context.iter() {
-            if !env.storage().instance().has(&DataKey::Policy(ctx.clone())) {
-                panic_with_error!(&env, MultiCliqueError::ContractPolicyDoesNotExist);
-            }
-            env.storage().instance().remove(&DataKey::Policy(ctx));
-        }
-        env.events()
-            .publish((POLICY, REMOVED), PolicyRemovedEventData { context });
+        bump_instance(&env);
+        let recovery = match Self::get_recovery(env.clone()) {
+            Some(recovery) => recovery,
+            None => panic_with_error!(&env, MultiCliqueError::NoRecoveryPending),
+        };
+        env.storage().instance().remove(&DataKey::Recovery);
+        env.events().publish(
+            (RECOVERY, CANCELLED),
+            RecoveryCancelledEventData {
+                signers: recovery.signers,
+                threshold: recovery.threshold,
+            },
+        );
     }
 
-    fn get_policies(env: Env, context: Vec<Address>) -> Vec<Address> {
-        let mut policies = Vec::new(&env);
-        for ctx in context.iter() {
-            if env.storage().instance().has(&DataKey::Policy(ctx.clone())) {
-                policies.push_back(
-                    env.storage()
-                        .instance()
-                        .get(&DataKey::Policy(ctx.clone()))
-                        .unwrap(),
-                );
-            }
+    fn execute_recovery(env: Env) {
+        bump_instance(&env);
+        let recovery = match Self::get_recovery(env.clone()) {
+            Some(recovery) => recovery,
+            None => panic_with_error!(&env, MultiCliqueError::NoRecoveryPending),
+        };
+        if env.ledger().sequence() < recovery.executable_at {
+            panic_with_error!(&env, MultiCliqueError::RecoveryDelayNotPassed);
         }
 
-        return policies;
+        // cliques, dedicated thresholds and session keys refer to the lost signer set, which may
+        // also have frozen the multiclique
+        let instance = env.storage().instance();
+        for public_key in Self::get_session_keys(env.clone()).iter() {
+            instance.remove(&DataKey::SessionKey(public_key.clone()));
+            instance.remove(&DataKey::SessionBudget(public_key));
+        }
+        instance.remove(&DataKey::SessionKeys);
+        instance.remove(&DataKey::Recovery);
+        instance.remove(&DataKey::Frozen);
+        instance.remove(&DataKey::FrozenUntil);
+        instance.remove(&DataKey::UnfreezeThreshold);
+        instance.remove(&DataKey::Cliques);
+        instance.remove(&DataKey::DefaultCliques);
+        instance.remove(&DataKey::GovThreshold);
+        instance.remove(&DataKey::DeployThreshold);
+        instance.set(&DataKey::Signers, &recovery.signers);
+        instance.set(&DataKey::DefaultThreshold, &recovery.threshold);
+
+        env.events().publish(
+            (RECOVERY, EXECUTED),
+            RecoveryExecutedEventData {
+                signers: recovery.signers,
+                threshold: recovery.threshold,
+            },
+        );
     }
 
-    #[allow(non_snake_case)]
-    fn __check_auth(
-        env: Env,
-        signature_payload: BytesN<32>,
-        signed_messages: Vec<SignedMessage>,
-        auth_context: Vec<Context>,
-    ) -> Result<(), MultiCliqueError> {
-        let signers: Vec<BytesN<32>> = env.storage().instance().get(&DataKey::Signers).unwrap();
-        for i in 0..signed_messages.len() {
-            let signature = signed_messages.get_unchecked(i);
-            // todo: In CustomAccount there is a prevSig check here, investigate / ask why
+    fn get_recovery(env: Env) -> Option<Recovery> {
+        env.storage().instance().get(&DataKey::Recovery)
+    }
 
-            if signers.first_index_of(&signature.public_key).is_none() {
-                panic_with_error!(&env, MultiCliqueError::UnknownSigner);
-            }
+    fn set_gov_threshold(env: Env, threshold: u32) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
 
-            env.crypto().ed25519_verify(
-                &signature.public_key,
-                &signature_payload.clone().into(),
-                &signature.signature,
-            );
-        }
-        let num_signers = signed_messages.len();
+        env.storage()
+            .instance()
+            .set(&DataKey::GovThreshold, &threshold);
+        validate_config(&env);
+        env.events()
+            .publish((GOV, THRESHOLD), GovThresholdChangedEventData { threshold });
+    }
 
-        for ctx in auth_context.iter() {
-            match ctx.clone() {
-                Context::Contract(contract_ctx) => {
-                    match env
-                        .storage()
-                        .instance()
-                        .get(&DataKey::Policy(contract_ctx.clone().contract))
-                    {
-                        Some(address) => {
-                            let policy = PolicyClient::new(&env, &address);
-                            let threshold = policy.get_threshold(
-                                &num_signers,
-                                &signers,
-                                &contract_ctx.contract,
-                                &contract_ctx.fn_name,
-                                &contract_ctx.args,
-                            );
-                            if threshold > num_signers {
-                                panic_with_error!(&env, MultiCliqueError::PolicyThresholdNotMet);
-                            }
-                            policy.run_policy(
-                                &num_signers,
-                                &signers,
-                                &contract_ctx.contract,
-                                &contract_ctx.fn_name,
-                                &contract_ctx.args,
-                            );
-                        }
-                        None => {
-                            let default_threshold = env
-                                .storage()
-                                .instance()
-                                .get(&DataKey::DefaultThreshold)
-                                .unwrap_or(0);
-                            if default_threshold > num_signers {
-                                panic_with_error!(&env, MultiCliqueError::DefaultThresholdNotMet);
-                            }
-                        }
-                    };
-                }
-                Context::CreateContractHostFn(_) => (),
-            }
+    fn get_gov_threshold(env: Env) -> u32 {
+        match env.storage().instance().get(&DataKey::GovThreshold) {
+            Some(threshold) => threshold,
+            None => Self::get_default_threshold(env),
         }
-        Ok(())
     }
-}
 
-#[contract]
+    fn set_deploy_threshold(env: Env, threshold: u32) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+
+        let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+        if threshold > permanent_weight(&signers) {
+            panic_with_error!(&env, MultiCliqueError::InvalidThreshold);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::DeployThreshold, &threshold);
+        env.events().publish(
+            (DEPLOY, CHANGED),
+            DeployThresholdChangedEventData { threshold },
+        );
+    }
+
+    fn get_deploy_threshold(env: Env) -> u32 {
+        match env.storage().instance().get(&DataKey::DeployThreshold) {
+            Some(threshold) => threshold,
+            None => Self::get_default_threshold(env),
+        }
+    }
+
+    fn set_deploy_policy(env: Env, policy: Address) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        env.storage()
+            .instance()
+            .set(&DataKey::DeployPolicy, &policy);
+        env.events()
+            .publish((DEPLOY, ADDED), DeployPolicyAddedEventData { policy });
+    }
+
+    fn remove_deploy_policy(env: Env) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let policy: Address = match env.storage().instance().get(&DataKey::DeployPolicy) {
+            Some(policy) => policy,
+            None => panic_with_error!(&env, MultiCliqueError::ContractPolicyDoesNotExist),
+        };
+        env.storage().instance().remove(&DataKey::DeployPolicy);
+        env.events()
+            .publish((DEPLOY, REMOVED), DeployPolicyRemovedEventData { policy });
+    }
+
+    fn get_deploy_policy(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::DeployPolicy)
+    }
+
+    fn create_clique(env: Env, name: Symbol, members: Vec<SignerKey>, quorum: u32) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+        let mut cliques = load_cliques(&env);
+
+        if cliques.contains_key(name.clone()) {
+            panic_with_error!(&env, MultiCliqueError::CliqueExists);
+        }
+
+        for i in 0..members.len() {
+            let member = members.get_unchecked(i);
+            if find_signer(&signers, &member).is_none() {
+                panic_with_error!(&env, MultiCliqueError::SignerDoesNotExist);
+            }
+            if members.first_index_of(&member) != Some(i) {
+                panic_with_error!(&env, MultiCliqueError::SignerAlreadyAdded);
+            }
+        }
+
+        let clique = Clique {
+            members: members.clone(),
+            quorum,
+        };
+        validate_quorum(&env, &signers, &clique);
+
+        cliques.set(name.clone(), clique);
+        env.storage().instance().set(&DataKey::Cliques, &cliques);
+        env.events().publish(
+            (CLIQUE, ADDED),
+            CliqueCreatedEventData {
+                name,
+                members,
+                quorum,
+            },
+        );
+    }
+
+    fn delete_clique(env: Env, name: Symbol) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let mut cliques = load_cliques(&env);
+
+        if !cliques.contains_key(name.clone()) {
+            panic_with_error!(&env, MultiCliqueError::CliqueDoesNotExist);
+        }
+
+        if Self::get_default_cliques(env.clone()).contains(&name) {
+            panic_with_error!(&env, MultiCliqueError::CliqueInUse);
+        }
+
+        cliques.remove(name.clone());
+        env.storage().instance().set(&DataKey::Cliques, &cliques);
+        env.events()
+            .publish((CLIQUE, REMOVED), CliqueDeletedEventData { name });
+    }
+
+    fn add_clique_member(env: Env, name: Symbol, member: SignerKey) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+        let mut cliques = load_cliques(&env);
+        let mut clique = get_clique(&env, &cliques, &name);
+
+        if find_signer(&signers, &member).is_none() {
+            panic_with_error!(&env, MultiCliqueError::SignerDoesNotExist);
+        }
+
+        if clique.members.contains(&member) {
+            panic_with_error!(&env, MultiCliqueError::SignerAlreadyAdded);
+        }
+
+        clique.members.push_back(member.clone());
+        cliques.set(name.clone(), clique);
+        env.storage().instance().set(&DataKey::Cliques, &cliques);
+        env.events()
+            .publish((MEMBER, ADDED), CliqueMemberAddedEventData { name, member });
+    }
+
+    fn remove_clique_member(env: Env, name: Symbol, member: SignerKey) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let mut cliques = load_cliques(&env);
+        let mut clique = get_clique(&env, &cliques, &name);
+
+        match clique.members.first_index_of(&member) {
+            None => panic_with_error!(&env, MultiCliqueError::SignerDoesNotExist),
+            Some(index) => clique.members.remove(index),
+        };
+
+        let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+        validate_quorum(&env, &signers, &clique);
+
+        cliques.set(name.clone(), clique);
+        env.storage().instance().set(&DataKey::Cliques, &cliques);
+        env.events().publish(
+            (MEMBER, REMOVED),
+            CliqueMemberRemovedEventData { name, member },
+        );
+    }
+
+    fn set_clique_quorum(env: Env, name: Symbol, quorum: u32) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let mut cliques = load_cliques(&env);
+        let mut clique = get_clique(&env, &cliques, &name);
+
+        clique.quorum = quorum;
+        let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+        validate_quorum(&env, &signers, &clique);
+
+        cliques.set(name.clone(), clique);
+        env.storage().instance().set(&DataKey::Cliques, &cliques);
+        env.events().publish(
+            (CLIQUE, CHANGED),
+            CliqueQuorumChangedEventData { name, quorum },
+        );
+    }
+
+    fn get_cliques(env: Env) -> Map<Symbol, Clique> {
+        load_cliques(&env)
+    }
+
+    fn set_default_cliques(env: Env, cliques: Vec<Symbol>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let existing = load_cliques(&env);
+        for name in cliques.iter() {
+            if !existing.contains_key(name) {
+                panic_with_error!(&env, MultiCliqueError::CliqueDoesNotExist);
+            }
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::DefaultCliques, &cliques);
+        env.events().publish(
+            (CLIQUE, DEFAULT),
+            DefaultCliquesChangedEventData { cliques },
+        );
+    }
+
+    fn get_default_cliques(env: Env) -> Vec<Symbol> {
+        env.storage()
+            .instance()
+            .get(&DataKey::DefaultCliques)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    fn attach_policy(env: Env, policy: Address, context: Vec<Address>, fn_names: Vec<Symbol>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        attach_policy(&env, policy, context, fn_names);
+    }
+
+    fn detach_policy(env: Env, context: Vec<Address>, fn_names: Vec<Symbol>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        detach_policy(&env, context, fn_names);
+    }
+
+    fn remove_policy(env: Env, policy: Address, context: Vec<Address>, fn_names: Vec<Symbol>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        for (ctx, fn_name) in bindings(&env, &context, &fn_names).iter() {
+            let mut policies = load_policies(&env, &ctx, &fn_name);
+            match policies.first_index_of(&policy) {
+                None => panic_with_error!(&env, MultiCliqueError::ContractPolicyDoesNotExist),
+                Some(index) => policies.remove(index),
+            };
+            store_policies(&env, &ctx, &fn_name, &policies);
+        }
+        env.events().publish(
+            (STACK, REMOVED),
+            PolicyStackRemovedEventData {
+                policy,
+                context,
+                fn_names,
+            },
+        );
+    }
+
+    fn reorder_policies(
+        env: Env,
+        context: Address,
+        fn_name: Option<Symbol>,
+        policies: Vec<Address>,
+    ) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let current = load_policies(&env, &context, &fn_name);
+        if current.len() != policies.len() {
+            panic_with_error!(&env, MultiCliqueError::InvalidPolicyOrder);
+        }
+        for i in 0..policies.len() {
+            let policy = policies.get_unchecked(i);
+            if !current.contains(&policy) || policies.first_index_of(&policy) != Some(i) {
+                panic_with_error!(&env, MultiCliqueError::InvalidPolicyOrder);
+            }
+        }
+        store_policies(&env, &context, &fn_name, &policies);
+        env.events().publish(
+            (STACK, CHANGED),
+            PolicyStackChangedEventData {
+                context,
+                fn_names: fn_name.map_or(Vec::new(&env), |fn_name| vec![&env, fn_name]),
+                policies,
+            },
+        );
+    }
+
+    fn get_policies(env: Env, context: Vec<Address>, fn_names: Vec<Symbol>) -> Vec<Address> {
+        let mut policies = Vec::new(&env);
+        for (ctx, fn_name) in bindings(&env, &context, &fn_names).iter() {
+            policies.append(&load_policies(&env, &ctx, &fn_name));
+        }
+
+        policies
+    }
+
+    fn set_fallback_policy(env: Env, policy: Address) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        env.storage()
+            .instance()
+            .set(&DataKey::FallbackPolicy, &policy);
+        env.events().publish(
+            (FALLBACK, CHANGED),
+            FallbackPolicyChangedEventData { policy },
+        );
+    }
+
+    fn remove_fallback_policy(env: Env) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let policy: Address = match env.storage().instance().get(&DataKey::FallbackPolicy) {
+            Some(policy) => policy,
+            None => panic_with_error!(&env, MultiCliqueError::ContractPolicyDoesNotExist),
+        };
+        env.storage().instance().remove(&DataKey::FallbackPolicy);
+        env.events().publish(
+            (FALLBACK, REMOVED),
+            FallbackPolicyRemovedEventData { policy },
+        );
+    }
+
+    fn get_fallback_policy(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::FallbackPolicy)
+    }
+
+    fn set_strict_mode(env: Env, enabled: bool) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        env.storage().instance().set(&DataKey::StrictMode, &enabled);
+        env.events()
+            .publish((STRICT, CHANGED), StrictModeChangedEventData { enabled });
+    }
+
+    fn is_strict_mode(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::StrictMode)
+            .unwrap_or(false)
+    }
+
+    fn allow_contracts(env: Env, contracts: Vec<Address>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let mut allowlist = Self::get_allowlist(env.clone());
+        for contract in contracts.iter() {
+            if !allowlist.contains(&contract) {
+                allowlist.push_back(contract);
+            }
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Allowlist, &allowlist);
+        env.events()
+            .publish((ALLOW, ADDED), AllowlistAddedEventData { contracts });
+    }
+
+    fn disallow_contracts(env: Env, contracts: Vec<Address>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        let mut allowlist = Self::get_allowlist(env.clone());
+        for contract in contracts.iter() {
+            if let Some(index) = allowlist.first_index_of(&contract) {
+                allowlist.remove(index);
+            }
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Allowlist, &allowlist);
+        env.events()
+            .publish((ALLOW, REMOVED), AllowlistRemovedEventData { contracts });
+    }
+
+    fn get_allowlist(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Allowlist)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    fn get_policy_stack(env: Env, context: Address, fn_name: Option<Symbol>) -> Vec<Address> {
+        load_policies(&env, &context, &fn_name)
+    }
+
+    fn extend_ttl(env: Env) {
+        bump_instance(&env);
+    }
+
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        env.current_contract_address().require_auth();
+        bump_instance(&env);
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        env.events().publish(
+            (GOV, UPGRADED),
+            UpgradedEventData {
+                wasm_hash: new_wasm_hash,
+            },
+        );
+    }
+
+    fn version(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::Version).unwrap_or(1)
+    }
+
+    fn migrate(env: Env) {
+        let from = Self::version(env.clone());
+        if from >= VERSION {
+            return;
+        }
+
+        if from < 2 {
+            let legacy: Vec<BytesN<32>> = env.storage().instance().get(&DataKey::Signers).unwrap();
+            let mut signers = Vec::new(&env);
+            for public_key in legacy.iter() {
+                signers.push_back(Signer {
+                    key: SignerKey::Ed25519(public_key),
+                    weight: 1,
+                    expires_at: 0,
+                });
+            }
+            env.storage().instance().set(&DataKey::Signers, &signers);
+        } else if from < 4 {
+            let legacy: Vec<SignerV2> = env.storage().instance().get(&DataKey::Signers).unwrap();
+            let mut signers = Vec::new(&env);
+            for signer in legacy.iter() {
+                signers.push_back(Signer {
+                    key: signer.key,
+                    weight: signer.weight,
+                    expires_at: 0,
+                });
+            }
+            env.storage().instance().set(&DataKey::Signers, &signers);
+        }
+
+        // single policy bindings of version 2 can't be enumerated; `load_policies` reads them as
+        // a stack of one and `store_policies` rewrites them once their stack changes
+
+        env.storage().instance().set(&DataKey::Version, &VERSION);
+        bump_instance(&env);
+        env.events()
+            .publish((GOV, MIGRATED), MigratedEventData { from, to: VERSION });
+    }
+
+    #[allow(non_snake_case)]
+    fn __check_auth(
+        env: Env,
+        signature_payload: BytesN<32>,
+        signed_messages: Vec<SignedMessage>,
+        auth_context: Vec<Context>,
+    ) -> Result<(), MultiCliqueError> {
+        bump_instance(&env);
+
+        let frozen = Self::is_frozen(env.clone());
+
+        if let Some(SignedMessage::Session(message)) = signed_messages.first() {
+            if signed_messages.len() == 1 {
+                if frozen {
+                    panic_with_error!(&env, MultiCliqueError::Frozen);
+                }
+                check_session(&env, &signature_payload, &message, &auth_context);
+                return Ok(());
+            }
+        }
+
+        let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+        let mut signed = Vec::new(&env);
+        let mut signed_weight = 0;
+        let mut previous: Option<SignerKey> = None;
+        for i in 0..signed_messages.len() {
+            let message = signed_messages.get_unchecked(i);
+            let key = message.signer_key();
+
+            // signatures have to be strictly sorted by signer key, so every signer is counted once
+            if let Some(previous) = previous {
+                if previous == key {
+                    panic_with_error!(&env, MultiCliqueError::DuplicateSigner);
+                }
+                if previous > key {
+                    panic_with_error!(&env, MultiCliqueError::UnsortedSignatures);
+                }
+            }
+            previous = Some(key.clone());
+
+            let signer = match find_signer(&signers, &key) {
+                None => panic_with_error!(&env, MultiCliqueError::UnknownSigner),
+                Some(index) => signers.get_unchecked(index),
+            };
+
+            // expired signers are kept until pruned, but don't count anymore
+            if !is_live(&env, &signer) {
+                continue;
+            }
+
+            match message {
+                SignedMessage::Ed25519(message) => env.crypto().ed25519_verify(
+                    &message.public_key,
+                    &signature_payload.clone().into(),
+                    &message.signature,
+                ),
+                SignedMessage::Secp256r1(message) => {
+                    verify_passkey(&env, &signature_payload, &message)
+                }
+                SignedMessage::Address(address) => {
+                    address.require_auth_for_args(vec![&env, signature_payload.to_val()])
+                }
+                // session keys can't sign along with signers
+                SignedMessage::Session(_) => {
+                    panic_with_error!(&env, MultiCliqueError::UnknownSigner)
+                }
+            }
+            signed_weight += signer.weight;
+            signed.push_back(key);
+        }
+
+        let signers = live_signers(&env, &signers);
+        let gov_threshold: Option<u32> = env.storage().instance().get(&DataKey::GovThreshold);
+
+        for ctx in auth_context.iter() {
+            if frozen {
+                check_frozen(&env, &ctx, signed_weight, &signed);
+                continue;
+            }
+            match ctx.clone() {
+                Context::Contract(contract_ctx) => {
+                    // any single signer may freeze the multiclique in an emergency
+                    if contract_ctx.contract == env.current_contract_address()
+                        && contract_ctx.fn_name == Symbol::new(&env, "freeze")
+                    {
+                        if signed.is_empty() {
+                            panic_with_error!(&env, MultiCliqueError::DefaultThresholdNotMet);
+                        }
+                        continue;
+                    }
+
+                    // changes to the multiclique itself need the governance threshold, if set,
+                    // which then replaces the default threshold
+                    let gov_threshold = gov_threshold
+                        .filter(|_| contract_ctx.contract == env.current_contract_address());
+                    if let Some(threshold) = gov_threshold {
+                        if threshold > signed_weight {
+                            panic_with_error!(&env, MultiCliqueError::GovThresholdNotMet);
+                        }
+                    }
+
+                    let policies = context_policies(&env, &contract_ctx);
+                    match policies.is_empty() {
+                        false => run_policies(
+                            &env,
+                            &policies,
+                            signed_weight,
+                            &signers,
+                            &signed,
+                            &contract_ctx,
+                        ),
+                        true => {
+                            let default_threshold = env
+                                .storage()
+                                .instance()
+                                .get(&DataKey::DefaultThreshold)
+                                .unwrap_or(0);
+                            // a spend limit may only lower the threshold, even if the default
+                            // threshold has been lowered after the limit was set
+                            let threshold = spend_limit_threshold(&env, &contract_ctx)
+                                .unwrap_or(default_threshold)
+                                .min(default_threshold);
+                            if gov_threshold.is_none() && threshold > signed_weight {
+                                panic_with_error!(&env, MultiCliqueError::DefaultThresholdNotMet);
+                            }
+                            require_cliques(&env, &Self::get_default_cliques(env.clone()), &signed);
+                        }
+                    };
+                }
+                Context::CreateContractHostFn(deploy_ctx) => {
+                    check_deployment(&env, signed_weight, &signers, &signed, &deploy_ctx)
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[contract]
 struct Policy;
 
 /// see `MultiCliquePolicyTrait` for documentation
@@ -267,19 +1301,33 @@ struct Policy;
 impl MultiCliquePolicyTrait for Policy {
     fn get_threshold(
         _env: Env,
-        num_signers: u32,
-        _signers: Vec<BytesN<32>>,
+        signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
         _address: Address,
         _fn_name: Symbol,
         _args: Vec<Val>,
     ) -> u32 {
-        num_signers
+        signed_weight
+    }
+
+    fn get_required_cliques(
+        env: Env,
+        _signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
+        _address: Address,
+        _fn_name: Symbol,
+        _args: Vec<Val>,
+    ) -> Vec<Symbol> {
+        Vec::new(&env)
     }
 
     fn run_policy(
         _env: Env,
-        _num_signers: u32,
-        _signers: Vec<BytesN<32>>,
+        _signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
         _address: Address,
         _fn_name: Symbol,
         _args: Vec<Val>,
@@ -287,3 +1335,701 @@ impl MultiCliquePolicyTrait for Policy {
         // do nothing
     }
 }
+
+#[contract]
+struct DeployPolicy;
+
+/// see `MultiCliqueDeployPolicyTrait` for documentation
+#[contractimpl]
+impl MultiCliqueDeployPolicyTrait for DeployPolicy {
+    fn get_deployment_threshold(
+        _env: Env,
+        signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
+        _wasm_hash: BytesN<32>,
+        _salt: BytesN<32>,
+    ) -> u32 {
+        signed_weight
+    }
+
+    fn run_deployment_policy(
+        _env: Env,
+        _signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
+        _wasm_hash: BytesN<32>,
+        _salt: BytesN<32>,
+    ) {
+        // do nothing
+    }
+}
+
+/// Extends the lifetime of the contract instance and code to a year once less than half a year is left.
+///
+/// An entry can live for at most `BUMP_A_YEAR` ledgers counting the current one.
+fn bump_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .bump(BUMP_THRESHOLD, BUMP_A_YEAR - 1);
+}
+
+/// Validates a complete signer set and its default threshold, see `init`.
+fn validate_signers(env: &Env, signers: &Vec<Signer>, threshold: u32) {
+    if signers.len() > THRESHOLD_LIMIT {
+        panic_with_error!(env, MultiCliqueError::SignerLimitExceeded);
+    }
+
+    for i in 0..signers.len() {
+        let signer = signers.get_unchecked(i);
+        if signer.weight == 0 {
+            panic_with_error!(env, MultiCliqueError::InvalidSignerWeight);
+        }
+        validate_expiry(env, &signer);
+        validate_key(env, &signer.key);
+        if find_signer(signers, &signer.key) != Some(i) {
+            panic_with_error!(env, MultiCliqueError::SignerAlreadyAdded);
+        }
+    }
+
+    if threshold > permanent_weight(signers) {
+        panic_with_error!(env, MultiCliqueError::InvalidThreshold);
+    }
+}
+
+/// Returns the lower threshold of a token transfer or approval within the token's spend limit.
+///
+/// The amount is recorded as spent in the current period. Calls without a spend limit, to other
+/// functions, spending from another account or exceeding the limit return `None` and need the
+/// default threshold; they don't count towards the limit.
+fn spend_limit_threshold(env: &Env, ctx: &ContractContext) -> Option<u32> {
+    let spend_limit = Contract::get_spend_limit(env.clone(), ctx.contract.clone())?;
+    if ctx.fn_name != Symbol::new(env, "transfer") && ctx.fn_name != Symbol::new(env, "approve") {
+        return None;
+    }
+
+    // transfer(from, to, amount) and approve(from, spender, amount, expiration_ledger)
+    let from = Address::try_from_val(env, &ctx.args.get(0)?).ok()?;
+    if from != env.current_contract_address() {
+        return None;
+    }
+    let amount = i128::try_from_val(env, &ctx.args.get(2)?).ok()?;
+    if amount < 0 {
+        return None;
+    }
+    let mut spent = current_spent(env, &ctx.contract, &spend_limit);
+    spent.amount = spent
+        .amount
+        .checked_add(amount)
+        .filter(|total| *total <= spend_limit.limit)?;
+    env.storage()
+        .instance()
+        .set(&DataKey::Spent(ctx.contract.clone()), &spent);
+
+    Some(spend_limit.threshold)
+}
+
+/// Returns the amount spent within the current period of a spend limit.
+fn current_spent(env: &Env, token: &Address, spend_limit: &SpendLimit) -> Spent {
+    let sequence = env.ledger().sequence();
+    match env
+        .storage()
+        .instance()
+        .get::<DataKey, Spent>(&DataKey::Spent(token.clone()))
+    {
+        Some(spent) if sequence < spent.period_start.saturating_add(spend_limit.period) => spent,
+        _ => Spent {
+            amount: 0,
+            period_start: sequence,
+        },
+    }
+}
+
+/// Functions of the MultiClique that can still be authorized while it is frozen.
+const FROZEN_FNS: [&str; 5] = [
+    "unfreeze",
+    "add_signer",
+    "remove_signer",
+    "replace_signer",
+    "cancel_recovery",
+];
+
+/// Authorizes a context while the MultiClique is frozen.
+///
+/// Only unfreezing, rotating signers and cancelling a recovery are allowed, and they have to meet
+/// the unfreeze threshold as well as the default cliques.
+fn check_frozen(env: &Env, ctx: &Context, signed_weight: u32, signed: &Vec<SignerKey>) {
+    let allowed = match ctx {
+        Context::Contract(ctx) => {
+            ctx.contract == env.current_contract_address()
+                && FROZEN_FNS
+                    .iter()
+                    .any(|fn_name| ctx.fn_name == Symbol::new(env, fn_name))
+        }
+        Context::CreateContractHostFn(_) => false,
+    };
+    if !allowed {
+        panic_with_error!(env, MultiCliqueError::Frozen);
+    }
+    if Contract::get_unfreeze_threshold(env.clone()) > signed_weight {
+        panic_with_error!(env, MultiCliqueError::UnfreezeThresholdNotMet);
+    }
+    require_cliques(env, &Contract::get_default_cliques(env.clone()), signed);
+}
+
+/// Returns the policies a call has to pass.
+///
+/// A binding of the called function takes precedence over the contract-wide one, the fallback
+/// policy applies to calls without any. In strict mode calls to unreviewed contracts are denied.
+fn context_policies(env: &Env, ctx: &ContractContext) -> Vec<Address> {
+    let mut policies = load_policies(env, &ctx.contract, &Some(ctx.fn_name.clone()));
+    if policies.is_empty() {
+        policies = load_policies(env, &ctx.contract, &None);
+    }
+    // the contract itself is always reachable so that it can still be governed
+    if policies.is_empty()
+        && Contract::is_strict_mode(env.clone())
+        && ctx.contract != env.current_contract_address()
+        && !Contract::get_allowlist(env.clone()).contains(&ctx.contract)
+    {
+        panic_with_error!(env, MultiCliqueError::ContractNotAllowed);
+    }
+    if policies.is_empty() {
+        if let Some(policy) = Contract::get_fallback_policy(env.clone()) {
+            policies.push_back(policy);
+        }
+    }
+    policies
+}
+
+/// Authorizes calls signed by a session key alone.
+///
+/// Every context has to be a call within the key's scope; deployments and calls to the
+/// MultiClique itself are never in scope. Each authorization uses up one call of the budget, if any.
+///
+/// Session keys have no signing weight, so policy thresholds don't apply to them, but every call
+/// still runs through its policies, e.g. to count towards their spend limits. Calls to a token
+/// with a native spend limit have to stay within it.
+fn check_session(
+    env: &Env,
+    signature_payload: &BytesN<32>,
+    message: &Ed25519Signature,
+    auth_context: &Vec<Context>,
+) {
+    let session = match Contract::get_session_key(env.clone(), message.public_key.clone()) {
+        Some(session) => session,
+        None => panic_with_error!(env, MultiCliqueError::UnknownSigner),
+    };
+    if env.ledger().sequence() >= session.expires_at {
+        panic_with_error!(env, MultiCliqueError::SessionKeyExpired);
+    }
+
+    for ctx in auth_context.iter() {
+        let in_scope = match ctx {
+            Context::Contract(ctx) => {
+                ctx.contract != env.current_contract_address()
+                    && session.scope.contains(&SessionScope {
+                        contract: ctx.contract,
+                        fn_name: ctx.fn_name,
+                    })
+            }
+            Context::CreateContractHostFn(_) => false,
+        };
+        if !in_scope {
+            panic_with_error!(env, MultiCliqueError::SessionKeyOutOfScope);
+        }
+    }
+
+    env.crypto().ed25519_verify(
+        &message.public_key,
+        &signature_payload.clone().into(),
+        &message.signature,
+    );
+
+    let budget_key = DataKey::SessionBudget(message.public_key.clone());
+    if let Some(budget) = env.storage().instance().get::<DataKey, u32>(&budget_key) {
+        if budget == 0 {
+            panic_with_error!(env, MultiCliqueError::SessionBudgetExhausted);
+        }
+        env.storage().instance().set(&budget_key, &(budget - 1));
+    }
+
+    let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+    let signers = live_signers(env, &signers);
+    let signed = Vec::new(env);
+    for ctx in auth_context.iter() {
+        // deployments are never in scope
+        let ctx = match ctx {
+            Context::Contract(ctx) => ctx,
+            Context::CreateContractHostFn(_) => continue,
+        };
+        let policies = context_policies(env, &ctx);
+        for address in policies.iter() {
+            PolicyClient::new(env, &address).run_policy(
+                &0,
+                &signers,
+                &signed,
+                &ctx.contract,
+                &ctx.fn_name,
+                &ctx.args,
+            );
+        }
+        if policies.is_empty()
+            && Contract::get_spend_limit(env.clone(), ctx.contract.clone()).is_some()
+            && spend_limit_threshold(env, &ctx).is_none()
+        {
+            panic_with_error!(env, MultiCliqueError::DefaultThresholdNotMet);
+        }
+    }
+}
+
+/// Adds a signer, see `add_signer`.
+///
+/// The resulting configuration has to be checked with `validate_config`.
+fn add_signer(env: &Env, signer: Signer) {
+    let mut signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+
+    if find_signer(&signers, &signer.key).is_some() {
+        panic_with_error!(env, MultiCliqueError::SignerAlreadyAdded);
+    }
+
+    if signer.weight == 0 {
+        panic_with_error!(env, MultiCliqueError::InvalidSignerWeight);
+    }
+
+    validate_expiry(env, &signer);
+    validate_key(env, &signer.key);
+
+    signers.push_back(signer.clone());
+    env.storage().instance().set(&DataKey::Signers, &signers);
+    env.events()
+        .publish((SIGNER, ADDED), SignerAddedEventData { signer });
+}
+
+/// Removes a signer along with its clique memberships, see `remove_signer`.
+///
+/// The resulting configuration has to be checked with `validate_config`.
+fn remove_signer(env: &Env, signer: SignerKey) {
+    let mut signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+    match find_signer(&signers, &signer) {
+        None => panic_with_error!(env, MultiCliqueError::SignerDoesNotExist),
+        Some(index) => signers.remove(index),
+    };
+
+    let mut cliques = load_cliques(env);
+    for (name, mut clique) in cliques.iter() {
+        if let Some(index) = clique.members.first_index_of(&signer) {
+            clique.members.remove(index);
+            cliques.set(name.clone(), clique);
+            env.events().publish(
+                (MEMBER, REMOVED),
+                CliqueMemberRemovedEventData {
+                    name,
+                    member: signer.clone(),
+                },
+            );
+        }
+    }
+    env.storage().instance().set(&DataKey::Cliques, &cliques);
+
+    env.storage().instance().set(&DataKey::Signers, &signers);
+    env.events()
+        .publish((SIGNER, REMOVED), SignerRemovedEventData { signer });
+}
+
+/// Replaces a signer in place, handing its clique memberships over to the new key.
+///
+/// The resulting configuration has to be checked with `validate_config`.
+fn replace_signer(env: &Env, old: SignerKey, new: Signer) {
+    let mut signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+    let index = match find_signer(&signers, &old) {
+        None => panic_with_error!(env, MultiCliqueError::SignerDoesNotExist),
+        Some(index) => index,
+    };
+    if new.key != old && find_signer(&signers, &new.key).is_some() {
+        panic_with_error!(env, MultiCliqueError::SignerAlreadyAdded);
+    }
+    if new.weight == 0 {
+        panic_with_error!(env, MultiCliqueError::InvalidSignerWeight);
+    }
+    validate_expiry(env, &new);
+    validate_key(env, &new.key);
+    signers.set(index, new.clone());
+    env.storage().instance().set(&DataKey::Signers, &signers);
+
+    let mut cliques = load_cliques(env);
+    for (name, mut clique) in cliques.iter() {
+        if let Some(index) = clique.members.first_index_of(&old) {
+            clique.members.set(index, new.key.clone());
+            cliques.set(name, clique);
+        }
+    }
+    env.storage().instance().set(&DataKey::Cliques, &cliques);
+
+    env.events().publish(
+        (SIGNER, REMOVED),
+        SignerRemovedEventData {
+            signer: old.clone(),
+        },
+    );
+    env.events().publish(
+        (SIGNER, ADDED),
+        SignerAddedEventData {
+            signer: new.clone(),
+        },
+    );
+    env.events()
+        .publish((SIGNER, REPLACED), SignerReplacedEventData { old, new });
+}
+
+/// Sets the default threshold, see `set_default_threshold`.
+///
+/// The resulting configuration has to be checked with `validate_config`.
+fn set_default_threshold(env: &Env, threshold: u32) {
+    env.storage()
+        .instance()
+        .set(&DataKey::DefaultThreshold, &threshold);
+    env.events().publish(
+        (GOV, CHANGED),
+        DefaultThresholdChangedEventData { threshold },
+    );
+}
+
+/// Attaches a policy to the given bindings, see `attach_policy`.
+fn attach_policy(env: &Env, policy: Address, context: Vec<Address>, fn_names: Vec<Symbol>) {
+    for (ctx, fn_name) in bindings(env, &context, &fn_names).iter() {
+        let mut policies = load_policies(env, &ctx, &fn_name);
+        if policies.contains(&policy) {
+            panic_with_error!(env, MultiCliqueError::ContractPolicyExists);
+        }
+        if policies.len() == POLICY_LIMIT {
+            panic_with_error!(env, MultiCliqueError::PolicyLimitExceeded);
+        }
+        policies.push_back(policy.clone());
+        store_policies(env, &ctx, &fn_name, &policies);
+    }
+    env.events().publish(
+        (POLICY, ADDED),
+        PolicyAddedEventData {
+            policy,
+            context,
+            fn_names,
+        },
+    );
+}
+
+/// Detaches all policies from the given bindings, see `detach_policy`.
+fn detach_policy(env: &Env, context: Vec<Address>, fn_names: Vec<Symbol>) {
+    for (ctx, fn_name) in bindings(env, &context, &fn_names).iter() {
+        if load_policies(env, &ctx, &fn_name).is_empty() {
+            panic_with_error!(env, MultiCliqueError::ContractPolicyDoesNotExist);
+        }
+        store_policies(env, &ctx, &fn_name, &Vec::new(env));
+    }
+    env.events().publish(
+        (POLICY, REMOVED),
+        PolicyRemovedEventData { context, fn_names },
+    );
+}
+
+/// Ensures the signers stay within the signer limit and the signers that never expire can still
+/// meet every threshold and every clique its quorum.
+fn validate_config(env: &Env) {
+    let signers: Vec<Signer> = env.storage().instance().get(&DataKey::Signers).unwrap();
+    if signers.len() > THRESHOLD_LIMIT {
+        panic_with_error!(env, MultiCliqueError::SignerLimitExceeded);
+    }
+
+    let threshold = Contract::get_default_threshold(env.clone())
+        .max(Contract::get_deploy_threshold(env.clone()))
+        .max(Contract::get_gov_threshold(env.clone()))
+        .max(Contract::get_unfreeze_threshold(env.clone()));
+    if permanent_weight(&signers) < threshold {
+        panic_with_error!(env, MultiCliqueError::InvalidThreshold);
+    }
+
+    // a governance threshold replaces the default threshold for calls to the multiclique itself,
+    // so it must never be the cheaper way to authorize them
+    let gov_threshold: Option<u32> = env.storage().instance().get(&DataKey::GovThreshold);
+    if let Some(gov_threshold) = gov_threshold {
+        if gov_threshold == 0 || gov_threshold < Contract::get_default_threshold(env.clone()) {
+            panic_with_error!(env, MultiCliqueError::InvalidThreshold);
+        }
+    }
+
+    for clique in load_cliques(env).values().iter() {
+        validate_quorum(env, &signers, &clique);
+    }
+}
+
+/// Expands contexts and function names into the bindings they address.
+///
+/// Without function names every context is addressed as a whole, otherwise each of its functions.
+fn bindings(
+    env: &Env,
+    context: &Vec<Address>,
+    fn_names: &Vec<Symbol>,
+) -> Vec<(Address, Option<Symbol>)> {
+    let mut bindings = Vec::new(env);
+    for ctx in context.iter() {
+        if fn_names.is_empty() {
+            bindings.push_back((ctx.clone(), None));
+        }
+        for fn_name in fn_names.iter() {
+            bindings.push_back((ctx.clone(), Some(fn_name)));
+        }
+    }
+    bindings
+}
+
+/// Returns the storage key of the policy stack bound to a context or one of its functions.
+fn policies_key(context: &Address, fn_name: &Option<Symbol>) -> DataKey {
+    match fn_name {
+        Some(fn_name) => DataKey::FnPolicies(context.clone(), fn_name.clone()),
+        None => DataKey::Policies(context.clone()),
+    }
+}
+
+/// Returns the ordered stack of policies bound to a context or one of its functions.
+///
+/// Single policies attached by version 2 of the contract are returned as a stack of one.
+fn load_policies(env: &Env, context: &Address, fn_name: &Option<Symbol>) -> Vec<Address> {
+    if let Some(policies) = env
+        .storage()
+        .instance()
+        .get(&policies_key(context, fn_name))
+    {
+        return policies;
+    }
+    if fn_name.is_some() {
+        return Vec::new(env);
+    }
+    match env
+        .storage()
+        .instance()
+        .get::<DataKey, Address>(&DataKey::Policy(context.clone()))
+    {
+        Some(policy) => vec![env, policy],
+        None => Vec::new(env),
+    }
+}
+
+/// Stores the ordered stack of policies of a binding, removing the binding if it is empty.
+fn store_policies(env: &Env, context: &Address, fn_name: &Option<Symbol>, policies: &Vec<Address>) {
+    if fn_name.is_none() {
+        env.storage()
+            .instance()
+            .remove(&DataKey::Policy(context.clone()));
+    }
+    let key = policies_key(context, fn_name);
+    if policies.is_empty() {
+        env.storage().instance().remove(&key);
+    } else {
+        env.storage().instance().set(&key, policies);
+    }
+}
+
+/// Runs a stack of policies for a contract call.
+///
+/// The highest threshold of all policies has to be met, as well as every clique any policy requires.
+/// Afterwards every policy is run in order.
+fn run_policies(
+    env: &Env,
+    policies: &Vec<Address>,
+    signed_weight: u32,
+    signers: &Vec<Signer>,
+    signed: &Vec<SignerKey>,
+    ctx: &ContractContext,
+) {
+    let mut threshold = 0;
+    for address in policies.iter() {
+        let policy = PolicyClient::new(env, &address);
+        threshold = threshold.max(policy.get_threshold(
+            &signed_weight,
+            signers,
+            signed,
+            &ctx.contract,
+            &ctx.fn_name,
+            &ctx.args,
+        ));
+    }
+    if threshold > signed_weight {
+        panic_with_error!(env, MultiCliqueError::PolicyThresholdNotMet);
+    }
+
+    for address in policies.iter() {
+        let policy = PolicyClient::new(env, &address);
+        require_cliques(
+            env,
+            &policy.get_required_cliques(
+                &signed_weight,
+                signers,
+                signed,
+                &ctx.contract,
+                &ctx.fn_name,
+                &ctx.args,
+            ),
+            signed,
+        );
+    }
+
+    for address in policies.iter() {
+        PolicyClient::new(env, &address).run_policy(
+            &signed_weight,
+            signers,
+            signed,
+            &ctx.contract,
+            &ctx.fn_name,
+            &ctx.args,
+        );
+    }
+}
+
+/// Checks a contract deployment against the deploy threshold, the default cliques and the deploy
+/// policy, if any.
+///
+/// The higher of both thresholds and the default cliques have to be met before the policy is run.
+fn check_deployment(
+    env: &Env,
+    signed_weight: u32,
+    signers: &Vec<Signer>,
+    signed: &Vec<SignerKey>,
+    ctx: &CreateContractHostFnContext,
+) {
+    let ContractExecutable::Wasm(wasm_hash) = ctx.executable.clone();
+    let mut threshold = Contract::get_deploy_threshold(env.clone());
+    let policy = Contract::get_deploy_policy(env.clone()).map(|address| {
+        let policy = DeployPolicyClient::new(env, &address);
+        threshold = threshold.max(policy.get_deployment_threshold(
+            &signed_weight,
+            signers,
+            signed,
+            &wasm_hash,
+            &ctx.salt,
+        ));
+        policy
+    });
+    if threshold > signed_weight {
+        panic_with_error!(env, MultiCliqueError::DeployThresholdNotMet);
+    }
+    require_cliques(env, &Contract::get_default_cliques(env.clone()), signed);
+    if let Some(policy) = policy {
+        policy.run_deployment_policy(&signed_weight, signers, signed, &wasm_hash, &ctx.salt);
+    }
+}
+
+/// Returns the index of the signer with the given key, if any.
+fn find_signer(signers: &Vec<Signer>, key: &SignerKey) -> Option<u32> {
+    signers
+        .iter()
+        .position(|signer| &signer.key == key)
+        .map(|index| index as u32)
+}
+
+/// Returns the summed weight of all given signers that never expire.
+///
+/// Thresholds are validated against this weight, so expiring signers can't lock the MultiClique.
+fn permanent_weight(signers: &Vec<Signer>) -> u32 {
+    signers
+        .iter()
+        .filter(|signer| signer.expires_at == 0)
+        .map(|signer| signer.weight)
+        .sum()
+}
+
+/// Ensures a clique has a quorum its members that never expire can meet on their own.
+fn validate_quorum(env: &Env, signers: &Vec<Signer>, clique: &Clique) {
+    let permanent_members = clique
+        .members
+        .iter()
+        .filter(|member| {
+            find_signer(signers, member)
+                .is_some_and(|index| signers.get_unchecked(index).expires_at == 0)
+        })
+        .count() as u32;
+    if clique.quorum == 0 || clique.quorum > permanent_members {
+        panic_with_error!(env, MultiCliqueError::InvalidThreshold);
+    }
+}
+
+/// Returns all given signers that have not expired yet.
+fn live_signers(env: &Env, signers: &Vec<Signer>) -> Vec<Signer> {
+    let mut live = Vec::new(env);
+    for signer in signers.iter() {
+        if is_live(env, &signer) {
+            live.push_back(signer);
+        }
+    }
+    live
+}
+
+/// Checks whether a signer has not expired yet.
+fn is_live(env: &Env, signer: &Signer) -> bool {
+    signer.expires_at == 0 || env.ledger().sequence() < signer.expires_at
+}
+
+/// Ensures no passkey signer is added if the contract was built without the `passkeys` feature,
+/// as it could never sign.
+fn validate_key(env: &Env, key: &SignerKey) {
+    if cfg!(not(any(test, feature = "passkeys"))) && matches!(key, SignerKey::Secp256r1(_)) {
+        panic_with_error!(env, MultiCliqueError::PasskeysDisabled);
+    }
+}
+
+/// Verifies a passkey assertion over the signature payload, see `webauthn::verify`.
+#[cfg(any(test, feature = "passkeys"))]
+fn verify_passkey(env: &Env, signature_payload: &BytesN<32>, message: &WebAuthnSignature) {
+    webauthn::verify(
+        env,
+        &message.public_key,
+        signature_payload,
+        &message.authenticator_data,
+        &message.client_data_json,
+        &message.signature,
+    );
+}
+
+/// Passkeys are only verified if the contract is built with the `passkeys` feature.
+#[cfg(not(any(test, feature = "passkeys")))]
+fn verify_passkey(env: &Env, _signature_payload: &BytesN<32>, _message: &WebAuthnSignature) {
+    panic_with_error!(env, MultiCliqueError::PasskeysDisabled);
+}
+
+/// Ensures a signer is not already expired when it's added.
+fn validate_expiry(env: &Env, signer: &Signer) {
+    if !is_live(env, signer) {
+        panic_with_error!(env, MultiCliqueError::InvalidSignerExpiry);
+    }
+}
+
+/// Returns all cliques of the contract, keyed by their name.
+fn load_cliques(env: &Env) -> Map<Symbol, Clique> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Cliques)
+        .unwrap_or(Map::new(env))
+}
+
+/// Returns the clique with the given name or panics if it does not exist.
+fn get_clique(env: &Env, cliques: &Map<Symbol, Clique>, name: &Symbol) -> Clique {
+    match cliques.get(name.clone()) {
+        None => panic_with_error!(env, MultiCliqueError::CliqueDoesNotExist),
+        Some(clique) => clique,
+    }
+}
+
+/// Panics unless every named clique reached its quorum among the keys that actually signed.
+fn require_cliques(env: &Env, names: &Vec<Symbol>, signed: &Vec<SignerKey>) {
+    let cliques = load_cliques(env);
+    for name in names.iter() {
+        let clique = get_clique(env, &cliques, &name);
+        let approvals = clique
+            .members
+            .iter()
+            .filter(|member| signed.contains(member))
+            .count() as u32;
+        if approvals < clique.quorum {
+            panic_with_error!(env, MultiCliqueError::CliqueQuorumNotMet);
+        }
+    }
+}
@@ -1,28 +1,66 @@
 #![cfg(test)]
 
-use ed25519_dalek::{Keypair, Signer};
-use rand::rngs::OsRng;
+use ed25519_dalek::{Keypair, Signer as _};
 use hex::decode;
+use rand::rngs::OsRng;
 
-use soroban_sdk::auth::{Context, ContractContext};
-use soroban_sdk::testutils::{Address as _, BytesN as _, Events as _};
-use soroban_sdk::{vec, Address, BytesN, Env, IntoVal, Symbol, Val, Vec};
+use p256::ecdsa::signature::hazmat::PrehashSigner;
+use p256::ecdsa::{Signature as P256Signature, SigningKey};
+use soroban_sdk::auth::{
+    Context, ContractContext, ContractExecutable, CreateContractHostFnContext,
+};
+use soroban_sdk::testutils::{Address as _, BytesN as _, Events as _, Ledger as _};
+use soroban_sdk::xdr::{
+    self, ContractIdPreimage, ContractIdPreimageFromAddress, CreateContractArgs, Hash,
+    HashIdPreimage, HashIdPreimageSorobanAuthorization, LedgerKey, ScAddress, ScVal,
+    SorobanAddressCredentials, SorobanAuthorizationEntry, SorobanAuthorizedFunction,
+    SorobanAuthorizedInvocation, SorobanCredentials, Uint256, WriteXdr,
+};
+use soroban_sdk::{
+    contract, contractimpl, vec, Address, Bytes, BytesN, Env, IntoVal, Symbol, TryFromVal, Val, Vec,
+};
 
 use crate::errors::MultiCliqueError;
-use crate::{Contract, ContractClient, SignedMessage};
+use crate::events::{
+    ConfigUpdatedEventData, PolicyAddedEventData, PolicyRemovedEventData, SignerRemovedEventData,
+    SignerReplacedEventData,
+};
+use crate::webauthn::base64_url_encode;
+use crate::{
+    ConfigOp, Contract, ContractClient, DataKey, Ed25519Signature, SessionScope, SignedMessage,
+    SignerV2, WebAuthnSignature, BUMP_A_YEAR, BUMP_THRESHOLD, MIN_RECOVERY_DELAY, POLICY_LIMIT,
+    THRESHOLD_LIMIT, VERSION,
+};
+use commons::traits::{MultiCliqueDeployPolicyTrait, MultiCliquePolicyTrait};
+use commons::types::{Signer, SignerKey};
 
 const ALICE_SECRET: &str = "be2161a67ad224bc3fc4237c30d8bf0ddbab03c0bcb9d186096df882e8f9d36cf1c3908c1f23e8b1e086c12a7a1a346f783821fc2dbffabed0cd974ab48eb6c2";
 const BOB_SECRET: &str = "2a4a6cf377240d0aad16513dce93b67cd356ca79ef509e80b6e71cbd569d499a8e5b4ee27e0c55a3facaa102c2a2211171a423afbbea89f68f688de5d52b2863";
+// sorted by public key: EVE < BOB < ALICE
 const EVE_SECRET: &str = "9ecd51618af6af2e1bbf600e5293546809d67f241afd476cc8fbb83c1a964b0b2658f2d0b1cc3a8925e519a834fd45fc366a68a98195262952241f583a695644";
 
+fn signer(e: &Env, secret: &str, weight: u32) -> Signer {
+    Signer {
+        key: SignerKey::Ed25519(
+            Keypair::from_bytes(&decode(secret).unwrap())
+                .unwrap()
+                .public
+                .to_bytes()
+                .into_val(e),
+        ),
+        weight,
+        expires_at: 0,
+    }
+}
+
 fn sign(e: &Env, signer: &Keypair, payload: &BytesN<32>) -> Val {
-    SignedMessage {
+    SignedMessage::Ed25519(Ed25519Signature {
         public_key: signer.public.to_bytes().into_val(e),
         signature: signer
             .sign(payload.to_array().as_slice())
             .to_bytes()
             .into_val(e),
-    }
+    })
     .into_val(e)
 }
 
@@ -31,11 +69,15 @@ struct Protocol {
     env: Env,
     client: ContractClient<'static>,
     threshold: u32,
-    signers: Vec<BytesN<32>>,
+    signers: Vec<Signer>,
 }
 
 impl Protocol {
     fn new(threshold: u32) -> Self {
+        Self::with_weights(threshold, 1, 1)
+    }
+
+    fn with_weights(threshold: u32, alice_weight: u32, bob_weight: u32) -> Self {
         let env = Env::default();
         env.budget().reset_unlimited();
         env.mock_all_auths();
@@ -44,16 +86,8 @@ impl Protocol {
         let client = ContractClient::new(&env, &protocol_address);
         let signers = vec![
             &env,
-            Keypair::from_bytes(&decode(ALICE_SECRET).unwrap())
-                .unwrap()
-                .public
-                .to_bytes()
-                .into_val(&env),
-            Keypair::from_bytes(&decode(BOB_SECRET).unwrap())
-                .unwrap()
-                .public
-                .to_bytes()
-                .into_val(&env),
+            signer(&env, ALICE_SECRET, alice_weight),
+            signer(&env, BOB_SECRET, bob_weight),
         ];
 
         client.init(&signers, &threshold);
@@ -122,8 +156,8 @@ fn test_default_threshold_met_but_wrong_signer() {
         &payload,
         &vec![
             &env,
-            sign(&env, &alice, &payload),
             sign(&env, &eve, &payload),
+            sign(&env, &alice, &payload),
         ],
         &vec![
             &env,
@@ -155,8 +189,8 @@ fn test_default_threshold_met() {
         &payload,
         &vec![
             &env,
-            sign(&env, &alice, &payload),
             sign(&env, &bob, &payload),
+            sign(&env, &alice, &payload),
         ],
         &vec![
             &env,
@@ -186,9 +220,7 @@ fn test_default_threshold_set_on_init() {
 fn test_add_signer() {
     let Protocol { client, env, .. } = Protocol::new(2);
     assert_eq!(client.get_signers().len(), 2);
-    let pair = Keypair::from_bytes(&decode(EVE_SECRET).unwrap()).unwrap();
-    let key = pair.public.to_bytes().into_val(&client.env);
-    client.add_signer(&key);
+    client.add_signer(&signer(&env, EVE_SECRET, 1));
     assert_eq!(client.get_signers().len(), 3);
     assert_eq!(env.events().all().len(), 2);
 }
@@ -198,7 +230,7 @@ fn test_remove_signer() {
     let Protocol { client, env, .. } = Protocol::new(1);
     assert_eq!(client.get_signers().len(), 2);
     let pair = Keypair::from_bytes(&decode(ALICE_SECRET).unwrap()).unwrap();
-    let key = pair.public.to_bytes().into_val(&client.env);
+    let key = SignerKey::Ed25519(pair.public.to_bytes().into_val(&client.env));
     client.remove_signer(&key);
     assert_eq!(client.get_signers().len(), 1);
     assert_eq!(env.events().all().len(), 2);
@@ -209,100 +241,2494 @@ fn test_remove_signer() {
 fn test_remove_signer_fails_if_not_exists() {
     let Protocol { client, env, .. } = Protocol::new(1);
     let pair = Keypair::from_bytes(&decode(EVE_SECRET).unwrap()).unwrap();
-    let key = pair.public.to_bytes().into_val(&client.env);
+    let key = SignerKey::Ed25519(pair.public.to_bytes().into_val(&client.env));
     client.remove_signer(&key);
     assert_eq!(env.events().all().len(), 2);
 }
 
 #[test]
-fn test_attach_policy() {
+fn test_replace_signer() {
     let Protocol { client, env, .. } = Protocol::new(2);
-    let policy = Address::random(&env);
-    let context = vec![&env, Address::random(&env)];
-    assert_eq!(client.get_policies(&context).len(), 0);
-    client.attach_policy(&policy, &context);
-    assert_eq!(client.get_policies(&context).len(), 1);
-    assert_eq!(env.events().all().len(), 2);
+    let alice = signer(&env, ALICE_SECRET, 1);
+    let eve = signer(&env, EVE_SECRET, 1);
+    let board = Symbol::new(&env, "board");
+    client.create_clique(
+        &board,
+        &vec![&env, alice.key.clone(), signer(&env, BOB_SECRET, 1).key],
+        &2,
+    );
+
+    client.replace_signer(&alice.key, &eve);
+    assert_eq!(
+        client.get_signers(),
+        vec![&env, eve.clone(), signer(&env, BOB_SECRET, 1)]
+    );
+    let members = client.get_cliques().get(board).unwrap().members;
+    assert!(members.contains(&eve.key));
+    assert!(!members.contains(&alice.key));
+
+    let (_, _, data) = env.events().all().last().unwrap();
+    let data: SignerReplacedEventData = data.into_val(&env);
+    assert_eq!(data.old, alice.key);
+    assert_eq!(data.new, eve);
+    assert_eq!(env.events().all().len(), 5);
 }
 
 #[test]
-#[should_panic(expected = "#1000")]
-fn test_attach_policy_fails_if_already_exists() {
+#[should_panic(expected = "#1009")]
+fn test_replace_signer_fails_if_new_signer_exists() {
     let Protocol { client, env, .. } = Protocol::new(2);
-    let policy = Address::random(&env);
-    let other = Address::random(&env);
-    let context = vec![&env, Address::random(&env)];
-    client.attach_policy(&policy, &context);
-    client.attach_policy(&other, &context);
+    client.replace_signer(
+        &signer(&env, ALICE_SECRET, 1).key,
+        &signer(&env, BOB_SECRET, 1),
+    );
 }
 
 #[test]
-fn test_detach_policy() {
+#[should_panic(expected = "#1008")]
+fn test_replace_signer_validates_threshold() {
+    let Protocol { client, env, .. } = Protocol::with_weights(3, 2, 1);
+    client.replace_signer(
+        &signer(&env, ALICE_SECRET, 2).key,
+        &signer(&env, EVE_SECRET, 1),
+    );
+}
+
+#[test]
+fn test_update_config() {
     let Protocol { client, env, .. } = Protocol::new(2);
+    let eve = signer(&env, EVE_SECRET, 1);
     let policy = Address::random(&env);
     let context = vec![&env, Address::random(&env)];
-    assert_eq!(client.get_policies(&context).len(), 0);
-    client.attach_policy(&policy, &context);
-    assert_eq!(client.get_policies(&context).len(), 1);
-    client.detach_policy(&context);
-    assert_eq!(client.get_policies(&context).len(), 0);
-    assert_eq!(env.events().all().len(), 3);
+
+    // removing both signers one by one would fail as the threshold can't be met in between
+    client.update_config(&vec![
+        &env,
+        ConfigOp::AddSigner(eve.clone()),
+        ConfigOp::RemoveSigner(signer(&env, ALICE_SECRET, 1).key),
+        ConfigOp::RemoveSigner(signer(&env, BOB_SECRET, 1).key),
+        ConfigOp::SetDefaultThreshold(1),
+        ConfigOp::AttachPolicy(policy.clone(), context.clone(), vec![&env]),
+    ]);
+    assert_eq!(client.get_signers(), vec![&env, eve]);
+    assert_eq!(client.get_default_threshold(), 1);
+    assert_eq!(
+        client.get_policies(&context, &vec![&env]),
+        vec![&env, policy]
+    );
+
+    // one event per operation plus the summary
+    assert_eq!(env.events().all().len(), 7);
+    let (_, _, data) = env.events().all().last().unwrap();
+    let data: ConfigUpdatedEventData = data.into_val(&env);
+    assert_eq!(
+        data,
+        ConfigUpdatedEventData {
+            operations: 5,
+            signers: 1,
+            threshold: 1,
+        }
+    );
 }
 
 #[test]
-#[should_panic(expected = "#1001")]
-fn test_detach_policy_fails_if_not_exists() {
+#[should_panic(expected = "#1008")]
+fn test_update_config_validates_final_state() {
     let Protocol { client, env, .. } = Protocol::new(2);
-    let context = vec![&env, Address::random(&env)];
-    client.detach_policy(&context);
+    client.update_config(&vec![
+        &env,
+        ConfigOp::AddSigner(signer(&env, EVE_SECRET, 1)),
+        ConfigOp::RemoveSigner(signer(&env, ALICE_SECRET, 1).key),
+        ConfigOp::RemoveSigner(signer(&env, BOB_SECRET, 1).key),
+    ]);
+}
+
+#[test]
+fn test_update_config_at_signer_limit() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let mut csprng = OsRng {};
+    for _ in 2..THRESHOLD_LIMIT {
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        client.add_signer(&Signer {
+            key: SignerKey::Ed25519(keypair.public.to_bytes().into_val(&env)),
+            weight: 1,
+            expires_at: 0,
+        });
+    }
+
+    // the limit only has to hold once every operation has been applied
+    client.update_config(&vec![
+        &env,
+        ConfigOp::AddSigner(signer(&env, EVE_SECRET, 1)),
+        ConfigOp::RemoveSigner(signer(&env, ALICE_SECRET, 1).key),
+    ]);
+    assert_eq!(client.get_signers().len(), THRESHOLD_LIMIT);
+}
+
+struct Guardians {
+    protocol: Protocol,
+    guardians: Vec<Address>,
+    new_signers: Vec<Signer>,
+}
+
+const RECOVERY_DELAY: u32 = MIN_RECOVERY_DELAY;
+
+fn with_guardians() -> Guardians {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let guardians = vec![
+        env,
+        Address::random(env),
+        Address::random(env),
+        Address::random(env),
+    ];
+    protocol
+        .client
+        .set_guardians(&guardians, &2, &RECOVERY_DELAY);
+    let new_signers = vec![env, signer(env, EVE_SECRET, 1)];
+    Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    }
+}
+
+#[test]
+fn test_recovery() {
+    let Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    } = with_guardians();
+    let Protocol { client, env, .. } = &protocol;
+    let approving = vec![env, guardians.get(0).unwrap(), guardians.get(2).unwrap()];
+    client.initiate_recovery(&approving, &new_signers, &1);
+    assert_eq!(env.auths().len(), 2);
+    assert_eq!(env.auths()[0].0, approving.get(0).unwrap());
+    assert_eq!(
+        client.get_recovery().unwrap().executable_at,
+        env.ledger().sequence() + RECOVERY_DELAY
+    );
+
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY - 1);
+    assert_eq!(
+        client.try_execute_recovery(),
+        Err(Ok(MultiCliqueError::RecoveryDelayNotPassed.into()))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    client.execute_recovery();
+    assert_eq!(client.get_signers(), new_signers);
+    assert_eq!(client.get_default_threshold(), 1);
+    assert_eq!(client.get_recovery(), None);
+
+    // the old keys are gone, the new one works
+    let invocation = check_auth(&protocol, &[&keypair(ALICE_SECRET)], transfer_context(env));
+    assert_eq!(invocation, Err(MultiCliqueError::UnknownSigner));
+    assert!(check_auth(&protocol, &[&keypair(EVE_SECRET)], transfer_context(env)).is_ok());
+}
+
+#[test]
+fn test_recovery_unfreezes() {
+    let Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    } = with_guardians();
+    let Protocol { client, env, .. } = &protocol;
+    client.set_unfreeze_threshold(&2);
+    client.freeze(&None);
+    let approving = vec![env, guardians.get(0).unwrap(), guardians.get(2).unwrap()];
+    client.initiate_recovery(&approving, &new_signers, &1);
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    client.execute_recovery();
+
+    assert!(!client.is_frozen());
+    assert_eq!(client.get_unfreeze_threshold(), 1);
+    assert!(check_auth(&protocol, &[&keypair(EVE_SECRET)], transfer_context(env)).is_ok());
+}
+
+#[test]
+fn test_recovery_removes_session_keys() {
+    let Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    } = with_guardians();
+    let Protocol { client, env, .. } = &protocol;
+    let bot = keypair(ALICE_SECRET);
+    let bot_key: BytesN<32> = bot.public.to_bytes().into_val(env);
+    client.add_session_key(
+        &bot_key,
+        &vec![
+            env,
+            SessionScope {
+                contract: Address::random(env),
+                fn_name: Symbol::new(env, "vote"),
+            },
+        ],
+        &(env.ledger().sequence() + 2 * RECOVERY_DELAY),
+        &Some(10),
+    );
+    let approving = vec![env, guardians.get(0).unwrap(), guardians.get(2).unwrap()];
+    client.initiate_recovery(&approving, &new_signers, &1);
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    client.execute_recovery();
+
+    assert_eq!(client.get_session_keys().len(), 0);
+    assert_eq!(client.get_session_key(&bot_key), None);
+    assert_eq!(client.get_session_budget(&bot_key), None);
+}
+
+#[test]
+#[should_panic(expected = "#1042")]
+fn test_recovery_delay_too_short_fails() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    client.set_guardians(&vec![&env, Address::random(&env)], &1, &0);
+}
+
+#[test]
+fn test_changing_guardians_cancels_recovery() {
+    let Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    } = with_guardians();
+    let Protocol { client, env, .. } = &protocol;
+    let approving = vec![env, guardians.get(0).unwrap(), guardians.get(2).unwrap()];
+    client.initiate_recovery(&approving, &new_signers, &1);
+    client.set_guardians(&vec![env, Address::random(env)], &1, &RECOVERY_DELAY);
+    assert_eq!(client.get_recovery(), None);
+
+    // disabling recovery doesn't need a delay
+    client.set_guardians(&vec![env], &0, &0);
+    assert_eq!(client.get_guardians().len(), 0);
+}
+
+#[test]
+fn test_recovery_can_be_cancelled() {
+    let Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    } = with_guardians();
+    let Protocol { client, env, .. } = &protocol;
+    client.initiate_recovery(&guardians, &new_signers, &1);
+    client.cancel_recovery();
+    assert_eq!(client.get_recovery(), None);
+
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    assert_eq!(
+        client.try_execute_recovery(),
+        Err(Ok(MultiCliqueError::NoRecoveryPending.into()))
+    );
+    assert_eq!(client.get_signers(), protocol.signers);
+}
+
+#[test]
+#[should_panic(expected = "#1026")]
+fn test_recovery_requires_guardian_threshold() {
+    let Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    } = with_guardians();
+    let approving = vec![&protocol.env, guardians.get(0).unwrap()];
+    protocol
+        .client
+        .initiate_recovery(&approving, &new_signers, &1);
+}
+
+#[test]
+#[should_panic(expected = "#1024")]
+fn test_recovery_requires_guardians() {
+    let Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    } = with_guardians();
+    let env = &protocol.env;
+    let approving = vec![env, guardians.get(0).unwrap(), Address::random(env)];
+    protocol
+        .client
+        .initiate_recovery(&approving, &new_signers, &1);
+}
+
+#[test]
+#[should_panic(expected = "#1027")]
+fn test_only_one_recovery_at_a_time() {
+    let Guardians {
+        protocol,
+        guardians,
+        new_signers,
+    } = with_guardians();
+    protocol
+        .client
+        .initiate_recovery(&guardians, &new_signers, &1);
+    protocol
+        .client
+        .initiate_recovery(&guardians, &new_signers, &1);
+}
+
+fn expiring_signer(e: &Env, secret: &str, ledgers: u32) -> Signer {
+    Signer {
+        expires_at: e.ledger().sequence() + ledgers,
+        ..signer(e, secret, 1)
+    }
+}
+
+#[test]
+fn test_expired_signers_are_ignored() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    protocol
+        .client
+        .add_signer(&expiring_signer(env, EVE_SECRET, 100));
+    assert!(check_auth(&protocol, &[&keypair(EVE_SECRET)], transfer_context(env)).is_ok());
+
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    let invocation = check_auth(&protocol, &[&keypair(EVE_SECRET)], transfer_context(env));
+    assert_eq!(invocation, Err(MultiCliqueError::DefaultThresholdNotMet));
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(EVE_SECRET), &keypair(BOB_SECRET)],
+        transfer_context(env),
+    );
+    assert!(invocation.is_ok());
+    assert_eq!(protocol.client.get_signers().len(), 3);
 }
 
 #[test]
 #[should_panic(expected = "#1008")]
-fn test_invalid_threshold_on_init_fails() {
-    Protocol::new(10);
+fn test_threshold_validation_ignores_expiring_signers() {
+    let Protocol { client, env, .. } = Protocol::new(1);
+    client.add_signer(&expiring_signer(&env, EVE_SECRET, 100));
+    client.set_default_threshold(&3);
 }
 
 #[test]
 #[should_panic(expected = "#1008")]
-fn test_invalid_threshold_on_update_fails() {
-    let Protocol { client, .. } = Protocol::new(2);
-    client.set_default_threshold(&10);
+fn test_clique_quorum_ignores_expiring_members() {
+    let Protocol { client, env, .. } = Protocol::new(1);
+    let eve = expiring_signer(&env, EVE_SECRET, 100);
+    client.add_signer(&eve);
+    client.create_clique(
+        &Symbol::new(&env, "audit"),
+        &vec![&env, eve.key, signer(&env, ALICE_SECRET, 1).key],
+        &2,
+    );
 }
 
 #[test]
-#[should_panic(expected = "#1002")]
-fn test_exceeding_signer_limit_on_update_fails() {
-    let Protocol { client, env, .. } = Protocol::new(2);
-    let mut csprng = OsRng{};
+#[should_panic(expected = "#1030")]
+fn test_add_expired_signer_fails() {
+    let Protocol { client, env, .. } = Protocol::new(1);
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    client.add_signer(&Signer {
+        expires_at: env.ledger().sequence(),
+        ..signer(&env, EVE_SECRET, 1)
+    });
+}
 
-    for _ in 0..15 {
-        let keypair: Keypair = Keypair::generate(&mut csprng);
-        let bytes = keypair.public.to_bytes().into_val(&env);
-        client.add_signer(&bytes);
+#[test]
+fn test_prune_expired_signers() {
+    let Protocol { client, env, .. } = Protocol::new(1);
+    let eve = expiring_signer(&env, EVE_SECRET, 100);
+    client.add_signer(&eve);
+    client.create_clique(
+        &Symbol::new(&env, "audit"),
+        &vec![&env, eve.key.clone(), signer(&env, ALICE_SECRET, 1).key],
+        &1,
+    );
+
+    client.prune_expired_signers();
+    assert_eq!(client.get_signers().len(), 3);
+
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    client.prune_expired_signers();
+    assert_eq!(client.get_signers(), protocol_signers(&env));
+    let (_, _, data) = env.events().all().last().unwrap();
+    let data: SignerRemovedEventData = data.into_val(&env);
+    assert_eq!(data.signer, eve.key);
+    let audit = client
+        .get_cliques()
+        .get(Symbol::new(&env, "audit"))
+        .unwrap();
+    assert_eq!(audit.members, vec![&env, signer(&env, ALICE_SECRET, 1).key]);
+}
+
+fn protocol_signers(e: &Env) -> Vec<Signer> {
+    vec![e, signer(e, ALICE_SECRET, 1), signer(e, BOB_SECRET, 1)]
+}
+
+struct Session {
+    protocol: Protocol,
+    votes: Address,
+    bot: Keypair,
+}
+
+fn with_session(budget: Option<u32>) -> Session {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let votes = Address::random(env);
+    let bot = keypair(EVE_SECRET);
+    protocol.client.add_session_key(
+        &bot.public.to_bytes().into_val(env),
+        &vec![
+            env,
+            SessionScope {
+                contract: votes.clone(),
+                fn_name: Symbol::new(env, "mark_implemented"),
+            },
+        ],
+        &(env.ledger().sequence() + 100),
+        &budget,
+    );
+    Session {
+        protocol,
+        votes,
+        bot,
     }
 }
 
+fn call(env: &Env, contract: &Address, fn_name: &str) -> Context {
+    Context::Contract(ContractContext {
+        contract: contract.clone(),
+        fn_name: Symbol::new(env, fn_name),
+        args: (1_u32,).into_val(env),
+    })
+}
+
+fn check_session_auth(
+    protocol: &Protocol,
+    session: &Keypair,
+    auth_context: Vec<Context>,
+) -> Result<(), MultiCliqueError> {
+    let env = &protocol.env;
+    let payload = BytesN::random(env);
+    let message = SignedMessage::Session(Ed25519Signature {
+        public_key: session.public.to_bytes().into_val(env),
+        signature: session
+            .sign(payload.to_array().as_slice())
+            .to_bytes()
+            .into_val(env),
+    });
+    env.try_invoke_contract_check_auth::<MultiCliqueError>(
+        &protocol.protocol_address.contract_id(),
+        &payload,
+        &vec![env, message.into_val(env)],
+        &auth_context,
+    )
+    .map_err(|err| err.unwrap())
+}
+
 #[test]
-#[should_panic(expected = "#1009")]
-fn test_signers_cannot_be_added_multiple_times() {
-    let Protocol { client, env, .. } = Protocol::new(2);
-    let candidate = Keypair::from_bytes(&decode(ALICE_SECRET).unwrap())
-        .unwrap()
-        .public
-        .to_bytes()
-        .into_val(&env);
-    client.add_signer(&candidate);
+fn test_session_key_scope() {
+    let Session {
+        protocol,
+        votes,
+        bot,
+    } = with_session(None);
+    let env = &protocol.env;
+    let invocation = check_session_auth(
+        &protocol,
+        &bot,
+        vec![env, call(env, &votes, "mark_implemented")],
+    );
+    assert!(invocation.is_ok());
+
+    let invocation = check_session_auth(
+        &protocol,
+        &bot,
+        vec![
+            env,
+            call(env, &votes, "mark_implemented"),
+            call(env, &votes, "vote"),
+        ],
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::SessionKeyOutOfScope));
+}
+
+#[test]
+fn test_session_key_cannot_change_configuration() {
+    let Session { protocol, bot, .. } = with_session(None);
+    let env = &protocol.env;
+    let invocation = check_session_auth(
+        &protocol,
+        &bot,
+        vec![env, call(env, &protocol.protocol_address, "add_signer")],
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::SessionKeyOutOfScope));
+
+    let result = protocol.client.try_add_session_key(
+        &BytesN::random(env),
+        &vec![
+            env,
+            SessionScope {
+                contract: protocol.protocol_address.clone(),
+                fn_name: Symbol::new(env, "add_signer"),
+            },
+        ],
+        &(env.ledger().sequence() + 100),
+        &None,
+    );
+    assert_eq!(
+        result,
+        Err(Ok(MultiCliqueError::SessionKeyOutOfScope.into()))
+    );
+}
+
+#[test]
+fn test_session_key_expiry() {
+    let Session {
+        protocol,
+        votes,
+        bot,
+    } = with_session(None);
+    let env = &protocol.env;
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    let invocation = check_session_auth(
+        &protocol,
+        &bot,
+        vec![env, call(env, &votes, "mark_implemented")],
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::SessionKeyExpired));
+}
+
+#[test]
+fn test_session_key_budget() {
+    let Session {
+        protocol,
+        votes,
+        bot,
+    } = with_session(Some(2));
+    let env = &protocol.env;
+    let public_key: BytesN<32> = bot.public.to_bytes().into_val(env);
+    let context = vec![env, call(env, &votes, "mark_implemented")];
+    assert!(check_session_auth(&protocol, &bot, context.clone()).is_ok());
+    assert!(check_session_auth(&protocol, &bot, context.clone()).is_ok());
+    assert_eq!(protocol.client.get_session_budget(&public_key), Some(0));
+    let invocation = check_session_auth(&protocol, &bot, context);
+    assert_eq!(invocation, Err(MultiCliqueError::SessionBudgetExhausted));
+}
+
+#[test]
+fn test_session_key_must_sign_alone() {
+    let Session { protocol, bot, .. } = with_session(None);
+    let env = &protocol.env;
+    let payload = BytesN::random(env);
+    let session = SignedMessage::Session(Ed25519Signature {
+        public_key: bot.public.to_bytes().into_val(env),
+        signature: bot
+            .sign(payload.to_array().as_slice())
+            .to_bytes()
+            .into_val(env),
+    });
+    let invocation = env
+        .try_invoke_contract_check_auth::<MultiCliqueError>(
+            &protocol.protocol_address.contract_id(),
+            &payload,
+            &vec![
+                env,
+                session.into_val(env),
+                sign(env, &keypair(BOB_SECRET), &payload),
+            ],
+            &vec![env, transfer_context(env)],
+        )
+        .map_err(|err| err.unwrap());
+    assert_eq!(invocation, Err(MultiCliqueError::UnknownSigner));
+}
+
+#[test]
+fn test_remove_session_key() {
+    let Session {
+        protocol,
+        votes,
+        bot,
+    } = with_session(None);
+    let env = &protocol.env;
+    let public_key: BytesN<32> = bot.public.to_bytes().into_val(env);
+    assert_eq!(
+        protocol.client.get_session_keys(),
+        vec![env, public_key.clone()]
+    );
+    protocol.client.remove_session_key(&public_key);
+    assert_eq!(protocol.client.get_session_key(&public_key), None);
+    let invocation = check_session_auth(
+        &protocol,
+        &bot,
+        vec![env, call(env, &votes, "mark_implemented")],
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::UnknownSigner));
+}
+
+#[test]
+fn test_single_signer_can_freeze() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let freeze = call(env, &protocol.protocol_address, "freeze");
+    assert!(check_auth(&protocol, &[&keypair(BOB_SECRET)], freeze).is_ok());
+    let unfreeze = call(env, &protocol.protocol_address, "unfreeze");
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], unfreeze);
+    assert_eq!(invocation, Err(MultiCliqueError::DefaultThresholdNotMet));
+}
+
+#[test]
+fn test_frozen() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let client = &protocol.client;
+    client.add_signer(&signer(env, EVE_SECRET, 1));
+    client.set_unfreeze_threshold(&3);
+    client.freeze(&None);
+    assert!(client.is_frozen());
+
+    let everyone = [
+        &keypair(EVE_SECRET),
+        &keypair(BOB_SECRET),
+        &keypair(ALICE_SECRET),
+    ];
+    let invocation = check_auth(&protocol, &everyone, transfer_context(env));
+    assert_eq!(invocation, Err(MultiCliqueError::Frozen));
+    let set_threshold = call(env, &protocol.protocol_address, "set_default_threshold");
+    let invocation = check_auth(&protocol, &everyone, set_threshold);
+    assert_eq!(invocation, Err(MultiCliqueError::Frozen));
+
+    // unfreezing and rotating signers need the unfreeze threshold
+    let unfreeze = call(env, &protocol.protocol_address, "unfreeze");
+    let invocation = check_auth(&protocol, &everyone[1..], unfreeze.clone());
+    assert_eq!(invocation, Err(MultiCliqueError::UnfreezeThresholdNotMet));
+    assert!(check_auth(&protocol, &everyone, unfreeze).is_ok());
+    let rotate = call(env, &protocol.protocol_address, "replace_signer");
+    assert!(check_auth(&protocol, &everyone, rotate).is_ok());
+
+    client.unfreeze();
+    assert!(!client.is_frozen());
+    assert!(check_auth(&protocol, &[&keypair(BOB_SECRET)], transfer_context(env)).is_ok());
+}
+
+#[test]
+fn test_frozen_requires_default_cliques() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let client = &protocol.client;
+    client.add_signer(&signer(env, EVE_SECRET, 1));
+    let core = Symbol::new(env, "core");
+    client.create_clique(&core, &vec![env, signer(env, ALICE_SECRET, 1).key], &1);
+    client.set_default_cliques(&vec![env, core]);
+    client.freeze(&None);
+
+    let unfreeze = call(env, &protocol.protocol_address, "unfreeze");
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(EVE_SECRET), &keypair(BOB_SECRET)],
+        unfreeze.clone(),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::CliqueQuorumNotMet));
+    let signers = [&keypair(EVE_SECRET), &keypair(ALICE_SECRET)];
+    assert!(check_auth(&protocol, &signers, unfreeze).is_ok());
+}
+
+#[test]
+fn test_frozen_allows_cancelling_recovery() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    protocol.client.freeze(&None);
+    let cancel = call(env, &protocol.protocol_address, "cancel_recovery");
+    let signers = [&keypair(BOB_SECRET), &keypair(ALICE_SECRET)];
+    assert!(check_auth(&protocol, &signers, cancel).is_ok());
 }
 
 #[test]
 #[should_panic(expected = "#1008")]
-fn test_signers_cannot_be_removed_if_threshold_not_reduced() {
-    let Protocol { client, env, .. } = Protocol::new(2);
-    let candidate = Keypair::from_bytes(&decode(ALICE_SECRET).unwrap())
-        .unwrap()
-        .public
-        .to_bytes()
-        .into_val(&env);
-    client.remove_signer(&candidate);
+fn test_unfreeze_threshold_below_gov_threshold_fails() {
+    let Protocol { client, .. } = Protocol::new(1);
+    client.set_gov_threshold(&2);
+    client.set_unfreeze_threshold(&1);
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_remove_signer_below_unfreeze_threshold_fails() {
+    let Protocol { client, env, .. } = Protocol::new(1);
+    client.set_unfreeze_threshold(&2);
+    client.remove_signer(&signer(&env, BOB_SECRET, 1).key);
+}
+
+#[test]
+fn test_unfreeze_threshold_follows_gov_threshold() {
+    let Protocol { client, env, .. } = Protocol::new(1);
+    client.add_signer(&signer(&env, EVE_SECRET, 1));
+    client.set_unfreeze_threshold(&2);
+    client.set_gov_threshold(&3);
+    assert_eq!(client.get_unfreeze_threshold(), 3);
+}
+
+#[test]
+fn test_default_unfreeze_threshold() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    assert_eq!(protocol.client.get_unfreeze_threshold(), 2);
+    protocol.client.freeze(&None);
+
+    let unfreeze = call(env, &protocol.protocol_address, "unfreeze");
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], unfreeze.clone());
+    assert_eq!(invocation, Err(MultiCliqueError::UnfreezeThresholdNotMet));
+    let signers = [&keypair(BOB_SECRET), &keypair(ALICE_SECRET)];
+    assert!(check_auth(&protocol, &signers, unfreeze).is_ok());
+
+    // capped by the weight of the signers that never expire
+    protocol.client.set_gov_threshold(&2);
+    assert_eq!(protocol.client.get_unfreeze_threshold(), 2);
+}
+
+#[test]
+fn test_freeze_for_the_longest_time() {
+    let Protocol { client, .. } = Protocol::new(1);
+    client.freeze(&Some(u32::MAX));
+    assert!(client.is_frozen());
+}
+
+#[test]
+fn test_frozen_rejects_session_keys() {
+    let Session {
+        protocol,
+        votes,
+        bot,
+    } = with_session(None);
+    let env = &protocol.env;
+    protocol.client.freeze(&None);
+    let invocation = check_session_auth(
+        &protocol,
+        &bot,
+        vec![env, call(env, &votes, "mark_implemented")],
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::Frozen));
+}
+
+#[test]
+fn test_automatic_unfreeze() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    protocol.client.freeze(&Some(10));
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], transfer_context(env));
+    assert_eq!(invocation, Err(MultiCliqueError::Frozen));
+
+    env.ledger().with_mut(|li| li.sequence_number += 10);
+    assert!(!protocol.client.is_frozen());
+    assert!(check_auth(&protocol, &[&keypair(BOB_SECRET)], transfer_context(env)).is_ok());
+}
+
+fn token_call(protocol: &Protocol, token: &Address, fn_name: &str, amount: i128) -> Context {
+    let env = &protocol.env;
+    let (from, to) = (protocol.protocol_address.clone(), Address::random(env));
+    Context::Contract(ContractContext {
+        contract: token.clone(),
+        fn_name: Symbol::new(env, fn_name),
+        args: match fn_name {
+            "approve" => (from, to, amount, 1000_u32).into_val(env),
+            _ => (from, to, amount).into_val(env),
+        },
+    })
+}
+
+#[test]
+fn test_native_spend_limit() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let token = Address::random(env);
+    let bob = keypair(BOB_SECRET);
+    let both = [&bob, &keypair(ALICE_SECRET)];
+    protocol.client.set_spend_limit(&token, &100, &50, &1);
+
+    let invocation = check_auth(
+        &protocol,
+        &[&bob],
+        token_call(&protocol, &token, "transfer", 60),
+    );
+    assert!(invocation.is_ok());
+    assert_eq!(protocol.client.get_spent(&token), 60);
+
+    // exceeding the limit needs the default threshold and doesn't count towards the limit
+    let invocation = check_auth(
+        &protocol,
+        &[&bob],
+        token_call(&protocol, &token, "transfer", 50),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::DefaultThresholdNotMet));
+    let invocation = check_auth(
+        &protocol,
+        &both,
+        token_call(&protocol, &token, "transfer", 50),
+    );
+    assert!(invocation.is_ok());
+    assert_eq!(protocol.client.get_spent(&token), 60);
+
+    let invocation = check_auth(
+        &protocol,
+        &[&bob],
+        token_call(&protocol, &token, "approve", 40),
+    );
+    assert!(invocation.is_ok());
+    assert_eq!(protocol.client.get_spent(&token), 100);
+
+    // other functions always need the default threshold
+    let invocation = check_auth(&protocol, &[&bob], token_call(&protocol, &token, "burn", 0));
+    assert_eq!(invocation, Err(MultiCliqueError::DefaultThresholdNotMet));
+
+    // the limit resets with the next period
+    env.ledger().with_mut(|li| li.sequence_number += 50);
+    assert_eq!(protocol.client.get_spent(&token), 0);
+    let invocation = check_auth(
+        &protocol,
+        &[&bob],
+        token_call(&protocol, &token, "transfer", 100),
+    );
+    assert!(invocation.is_ok());
+}
+
+#[test]
+fn test_native_spend_limit_ignores_negative_amounts() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let token = Address::random(env);
+    protocol.client.set_spend_limit(&token, &100, &50, &1);
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(BOB_SECRET)],
+        token_call(&protocol, &token, "transfer", -100),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::DefaultThresholdNotMet));
+}
+
+#[test]
+fn test_native_spend_limit_ignores_other_accounts() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let token = Address::random(env);
+    protocol.client.set_spend_limit(&token, &100, &50, &1);
+    let transfer = Context::Contract(ContractContext {
+        contract: token.clone(),
+        fn_name: Symbol::new(env, "transfer"),
+        args: (Address::random(env), Address::random(env), 10_i128).into_val(env),
+    });
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], transfer);
+    assert_eq!(invocation, Err(MultiCliqueError::DefaultThresholdNotMet));
+    assert_eq!(protocol.client.get_spent(&token), 0);
+}
+
+#[test]
+fn test_native_spend_limit_never_raises_default_threshold() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let token = Address::random(env);
+    protocol.client.set_spend_limit(&token, &100, &50, &2);
+    protocol.client.set_default_threshold(&1);
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(BOB_SECRET)],
+        token_call(&protocol, &token, "transfer", 10),
+    );
+    assert!(invocation.is_ok());
+}
+
+#[test]
+#[should_panic(expected = "#1038")]
+fn test_native_spend_limit_must_not_be_negative() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    client.set_spend_limit(&Address::random(&env), &-1, &50, &1);
+}
+
+#[test]
+fn test_attach_policy() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let policy = Address::random(&env);
+    let context = vec![&env, Address::random(&env)];
+    assert_eq!(client.get_policies(&context, &vec![&env]).len(), 0);
+    client.attach_policy(&policy, &context, &vec![&env]);
+    assert_eq!(client.get_policies(&context, &vec![&env]).len(), 1);
+    assert_eq!(env.events().all().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "#1000")]
+fn test_attach_policy_fails_if_already_exists() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let policy = Address::random(&env);
+    let context = vec![&env, Address::random(&env)];
+    client.attach_policy(&policy, &context, &vec![&env]);
+    client.attach_policy(&policy, &context, &vec![&env]);
+}
+
+#[test]
+fn test_attach_policy_stacks_policies() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let first = Address::random(&env);
+    let second = Address::random(&env);
+    let context = Address::random(&env);
+    client.attach_policy(&first, &vec![&env, context.clone()], &vec![&env]);
+    client.attach_policy(&second, &vec![&env, context.clone()], &vec![&env]);
+    assert_eq!(
+        client.get_policy_stack(&context, &None),
+        vec![&env, first.clone(), second.clone()]
+    );
+    assert_eq!(
+        client.get_policies(&vec![&env, context, Address::random(&env)], &vec![&env]),
+        vec![&env, first, second]
+    );
+}
+
+#[test]
+#[should_panic(expected = "#1019")]
+fn test_attach_policy_fails_if_limit_exceeded() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let context = vec![&env, Address::random(&env)];
+    for _ in 0..=POLICY_LIMIT {
+        client.attach_policy(&Address::random(&env), &context, &vec![&env]);
+    }
+}
+
+#[test]
+fn test_remove_policy() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let first = Address::random(&env);
+    let second = Address::random(&env);
+    let context = vec![&env, Address::random(&env)];
+    client.attach_policy(&first, &context, &vec![&env]);
+    client.attach_policy(&second, &context, &vec![&env]);
+    client.remove_policy(&first, &context, &vec![&env]);
+    assert_eq!(
+        client.get_policies(&context, &vec![&env]),
+        vec![&env, second.clone()]
+    );
+    client.remove_policy(&second, &context, &vec![&env]);
+    assert_eq!(client.get_policies(&context, &vec![&env]).len(), 0);
+    assert_eq!(env.events().all().len(), 5);
+}
+
+#[test]
+#[should_panic(expected = "#1001")]
+fn test_remove_policy_fails_if_not_attached() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let context = vec![&env, Address::random(&env)];
+    client.attach_policy(&Address::random(&env), &context, &vec![&env]);
+    client.remove_policy(&Address::random(&env), &context, &vec![&env]);
+}
+
+#[test]
+fn test_reorder_policies() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let first = Address::random(&env);
+    let second = Address::random(&env);
+    let context = Address::random(&env);
+    client.attach_policy(&first, &vec![&env, context.clone()], &vec![&env]);
+    client.attach_policy(&second, &vec![&env, context.clone()], &vec![&env]);
+    let reordered = vec![&env, second, first];
+    client.reorder_policies(&context, &None, &reordered);
+    assert_eq!(client.get_policy_stack(&context, &None), reordered);
+    assert_eq!(env.events().all().len(), 4);
+}
+
+#[test]
+#[should_panic(expected = "#1020")]
+fn test_reorder_policies_must_be_permutation() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let first = Address::random(&env);
+    let second = Address::random(&env);
+    let context = Address::random(&env);
+    client.attach_policy(&first, &vec![&env, context.clone()], &vec![&env]);
+    client.attach_policy(&second, &vec![&env, context.clone()], &vec![&env]);
+    client.reorder_policies(&context, &None, &vec![&env, first.clone(), first]);
+}
+
+#[test]
+fn test_legacy_policy_read_as_stack() {
+    let Protocol {
+        client,
+        env,
+        protocol_address,
+        ..
+    } = Protocol::new(2);
+    let legacy = Address::random(&env);
+    let other = Address::random(&env);
+    let context = Address::random(&env);
+
+    // single policy binding as written by version 2
+    env.as_contract(&protocol_address, || {
+        env.storage()
+            .instance()
+            .set(&DataKey::Policy(context.clone()), &legacy);
+    });
+    assert_eq!(
+        client.get_policy_stack(&context, &None),
+        vec![&env, legacy.clone()]
+    );
+
+    client.attach_policy(&other, &vec![&env, context.clone()], &vec![&env]);
+    assert_eq!(
+        client.get_policy_stack(&context, &None),
+        vec![&env, legacy, other]
+    );
+    env.as_contract(&protocol_address, || {
+        assert!(!env
+            .storage()
+            .instance()
+            .has(&DataKey::Policy(context.clone())));
+    });
+}
+
+#[test]
+fn test_detach_policy() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let policy = Address::random(&env);
+    let context = vec![&env, Address::random(&env)];
+    assert_eq!(client.get_policies(&context, &vec![&env]).len(), 0);
+    client.attach_policy(&policy, &context, &vec![&env]);
+    assert_eq!(client.get_policies(&context, &vec![&env]).len(), 1);
+    client.detach_policy(&context, &vec![&env]);
+    assert_eq!(client.get_policies(&context, &vec![&env]).len(), 0);
+    assert_eq!(env.events().all().len(), 3);
+}
+
+#[test]
+#[should_panic(expected = "#1001")]
+fn test_detach_policy_fails_if_not_exists() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let context = vec![&env, Address::random(&env)];
+    client.detach_policy(&context, &vec![&env]);
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_invalid_threshold_on_init_fails() {
+    Protocol::new(10);
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_invalid_threshold_on_update_fails() {
+    let Protocol { client, .. } = Protocol::new(2);
+    client.set_default_threshold(&10);
+}
+
+#[test]
+#[should_panic(expected = "#1002")]
+fn test_exceeding_signer_limit_on_update_fails() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let mut csprng = OsRng {};
+
+    for _ in 0..15 {
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let public_key = keypair.public.to_bytes().into_val(&env);
+        client.add_signer(&Signer {
+            key: SignerKey::Ed25519(public_key),
+            weight: 1,
+            expires_at: 0,
+        });
+    }
+}
+
+#[test]
+#[should_panic(expected = "#1009")]
+fn test_signers_cannot_be_added_multiple_times() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    client.add_signer(&signer(&env, ALICE_SECRET, 1));
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_signers_cannot_be_removed_if_threshold_not_reduced() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    client.remove_signer(&signer(&env, ALICE_SECRET, 1).key);
+}
+
+#[test]
+#[should_panic(expected = "#1010")]
+fn test_signers_cannot_have_zero_weight() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    client.add_signer(&signer(&env, EVE_SECRET, 0));
+}
+
+#[test]
+fn test_weighted_threshold_met_by_single_signer() {
+    let protocol = Protocol::with_weights(3, 3, 1);
+    let env = protocol.env;
+    let alice = Keypair::from_bytes(&decode(ALICE_SECRET).unwrap()).unwrap();
+
+    let payload = BytesN::random(&env);
+
+    let invocation = env.try_invoke_contract_check_auth::<MultiCliqueError>(
+        &protocol.protocol_address.contract_id(),
+        &payload,
+        &vec![&env, sign(&env, &alice, &payload)],
+        &vec![
+            &env,
+            Context::Contract(ContractContext {
+                contract: Address::random(&env),
+                fn_name: Symbol::new(&env, "transfer"),
+                args: ((), (), 100_i128).into_val(&env),
+            }),
+        ],
+    );
+    assert!(invocation.is_ok());
+}
+
+#[test]
+fn test_weighted_threshold_not_met() {
+    let protocol = Protocol::new(2);
+    let env = protocol.env;
+    protocol.client.add_signer(&signer(&env, EVE_SECRET, 2));
+    protocol.client.set_default_threshold(&3);
+    let alice = Keypair::from_bytes(&decode(ALICE_SECRET).unwrap()).unwrap();
+    let bob = Keypair::from_bytes(&decode(BOB_SECRET).unwrap()).unwrap();
+
+    let payload = BytesN::random(&env);
+
+    let invocation = env.try_invoke_contract_check_auth::<MultiCliqueError>(
+        &protocol.protocol_address.contract_id(),
+        &payload,
+        &vec![
+            &env,
+            sign(&env, &bob, &payload),
+            sign(&env, &alice, &payload),
+        ],
+        &vec![
+            &env,
+            Context::Contract(ContractContext {
+                contract: Address::random(&env),
+                fn_name: Symbol::new(&env, "transfer"),
+                args: ((), (), 100_i128).into_val(&env),
+            }),
+        ],
+    );
+    assert_eq!(
+        invocation.err().unwrap().unwrap(),
+        MultiCliqueError::DefaultThresholdNotMet
+    );
+}
+
+#[test]
+fn test_threshold_bounded_by_total_weight() {
+    let Protocol { client, .. } = Protocol::with_weights(2, 3, 2);
+    client.set_default_threshold(&5);
+    assert_eq!(client.get_default_threshold(), 5);
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_signers_cannot_be_removed_if_remaining_weight_too_low() {
+    let Protocol { client, env, .. } = Protocol::with_weights(2, 1, 2);
+    client.remove_signer(&signer(&env, BOB_SECRET, 2).key);
+}
+
+fn keypair(secret: &str) -> Keypair {
+    Keypair::from_bytes(&decode(secret).unwrap()).unwrap()
+}
+
+fn transfer_context(env: &Env) -> Context {
+    Context::Contract(ContractContext {
+        contract: Address::random(env),
+        fn_name: Symbol::new(env, "transfer"),
+        args: ((), (), 100_i128).into_val(env),
+    })
+}
+
+fn check_auth(
+    protocol: &Protocol,
+    keypairs: &[&Keypair],
+    context: Context,
+) -> Result<(), MultiCliqueError> {
+    let env = &protocol.env;
+    let payload = BytesN::random(env);
+    let mut signatures = Vec::new(env);
+    for keypair in keypairs {
+        signatures.push_back(sign(env, keypair, &payload));
+    }
+    env.try_invoke_contract_check_auth::<MultiCliqueError>(
+        &protocol.protocol_address.contract_id(),
+        &payload,
+        &signatures,
+        &vec![env, context],
+    )
+    .map_err(|err| err.unwrap())
+}
+
+fn with_board_and_finance() -> Protocol {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let eve = signer(env, EVE_SECRET, 1);
+    protocol.client.add_signer(&eve);
+    protocol.client.create_clique(
+        &Symbol::new(env, "board"),
+        &vec![
+            env,
+            signer(env, ALICE_SECRET, 1).key,
+            signer(env, BOB_SECRET, 1).key,
+        ],
+        &1,
+    );
+    protocol
+        .client
+        .create_clique(&Symbol::new(env, "finance"), &vec![env, eve.key], &1);
+    protocol.client.set_default_cliques(&vec![
+        env,
+        Symbol::new(env, "board"),
+        Symbol::new(env, "finance"),
+    ]);
+    protocol
+}
+
+#[test]
+fn test_create_clique() {
+    let protocol = with_board_and_finance();
+    let cliques = protocol.client.get_cliques();
+    assert_eq!(cliques.len(), 2);
+    assert_eq!(
+        cliques
+            .get(Symbol::new(&protocol.env, "board"))
+            .unwrap()
+            .quorum,
+        1
+    );
+    assert_eq!(protocol.client.get_default_cliques().len(), 2);
+    assert_eq!(protocol.env.events().all().len(), 5);
+}
+
+#[test]
+fn test_default_cliques_quorum_not_met() {
+    let protocol = with_board_and_finance();
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(BOB_SECRET), &keypair(ALICE_SECRET)],
+        transfer_context(&protocol.env),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::CliqueQuorumNotMet));
+}
+
+#[test]
+fn test_default_cliques_quorum_met() {
+    let protocol = with_board_and_finance();
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(EVE_SECRET), &keypair(ALICE_SECRET)],
+        transfer_context(&protocol.env),
+    );
+    assert!(invocation.is_ok());
+}
+
+#[test]
+#[should_panic(expected = "#1006")]
+fn test_clique_members_must_be_signers() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    client.create_clique(
+        &Symbol::new(&env, "board"),
+        &vec![&env, signer(&env, EVE_SECRET, 1).key],
+        &1,
+    );
+}
+
+#[test]
+#[should_panic(expected = "#1014")]
+fn test_clique_in_use_cannot_be_deleted() {
+    let protocol = with_board_and_finance();
+    protocol
+        .client
+        .delete_clique(&Symbol::new(&protocol.env, "board"));
+}
+
+#[test]
+fn test_remove_signer_removes_clique_membership() {
+    let protocol = with_board_and_finance();
+    let env = &protocol.env;
+    protocol
+        .client
+        .remove_signer(&signer(env, BOB_SECRET, 1).key);
+    let board = protocol
+        .client
+        .get_cliques()
+        .get(Symbol::new(env, "board"))
+        .unwrap();
+    assert_eq!(board.members.len(), 1);
+}
+
+#[test]
+fn test_duplicate_signatures_rejected() {
+    let protocol = Protocol::new(2);
+    let alice = keypair(ALICE_SECRET);
+    let invocation = check_auth(
+        &protocol,
+        &[&alice, &alice],
+        transfer_context(&protocol.env),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::DuplicateSigner));
+}
+
+#[test]
+fn test_duplicate_signatures_cannot_meet_weighted_threshold() {
+    let protocol = Protocol::with_weights(3, 2, 1);
+    let alice = keypair(ALICE_SECRET);
+    let invocation = check_auth(
+        &protocol,
+        &[&alice, &alice],
+        transfer_context(&protocol.env),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::DuplicateSigner));
+}
+
+#[test]
+fn test_unsorted_signatures_rejected() {
+    let protocol = Protocol::new(2);
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(ALICE_SECRET), &keypair(BOB_SECRET)],
+        transfer_context(&protocol.env),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::UnsortedSignatures));
+}
+
+/// Test policy that requires ALICE, acting as CFO, to co-sign every call.
+#[contract]
+struct CfoPolicy;
+
+#[contractimpl]
+impl MultiCliquePolicyTrait for CfoPolicy {
+    fn get_threshold(
+        env: Env,
+        signed_weight: u32,
+        _signers: Vec<Signer>,
+        signed: Vec<SignerKey>,
+        _address: Address,
+        _fn_name: Symbol,
+        _args: Vec<Val>,
+    ) -> u32 {
+        if signed.contains(&signer(&env, ALICE_SECRET, 1).key) {
+            return 1;
+        }
+        signed_weight + 1
+    }
+
+    fn get_required_cliques(
+        env: Env,
+        _signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
+        _address: Address,
+        _fn_name: Symbol,
+        _args: Vec<Val>,
+    ) -> Vec<Symbol> {
+        Vec::new(&env)
+    }
+
+    fn run_policy(
+        _env: Env,
+        _signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
+        _address: Address,
+        _fn_name: Symbol,
+        _args: Vec<Val>,
+    ) {
+    }
+}
+
+#[test]
+fn test_policy_sees_signing_subset() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let token = Address::random(env);
+    let policy = env.register_contract(None, CfoPolicy);
+    protocol
+        .client
+        .attach_policy(&policy, &vec![env, token.clone()], &vec![env]);
+
+    let context = Context::Contract(ContractContext {
+        contract: token,
+        fn_name: Symbol::new(env, "transfer"),
+        args: ((), (), 100_i128).into_val(env),
+    });
+
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], context.clone());
+    assert_eq!(invocation, Err(MultiCliqueError::PolicyThresholdNotMet));
+
+    let invocation = check_auth(&protocol, &[&keypair(ALICE_SECRET)], context);
+    assert!(invocation.is_ok());
+}
+
+#[test]
+fn test_function_policy_takes_precedence() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let token = Address::random(env);
+    let transfer = Symbol::new(env, "transfer");
+    let cfo = env.register_contract(None, CfoPolicy);
+    let contract_wide =
+        CountingPolicyClient::new(env, &env.register_contract(None, CountingPolicy));
+    contract_wide.set_threshold(&1);
+    protocol.client.attach_policy(
+        &cfo,
+        &vec![env, token.clone()],
+        &vec![env, transfer.clone()],
+    );
+    protocol.client.attach_policy(
+        &contract_wide.address,
+        &vec![env, token.clone()],
+        &vec![env],
+    );
+    assert_eq!(
+        protocol
+            .client
+            .get_policy_stack(&token, &Some(transfer.clone())),
+        vec![env, cfo.clone()]
+    );
+
+    let call = |fn_name: &str| {
+        Context::Contract(ContractContext {
+            contract: token.clone(),
+            fn_name: Symbol::new(env, fn_name),
+            args: ((), (), 100_i128).into_val(env),
+        })
+    };
+
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], call("transfer"));
+    assert_eq!(invocation, Err(MultiCliqueError::PolicyThresholdNotMet));
+    assert!(check_auth(&protocol, &[&keypair(ALICE_SECRET)], call("transfer")).is_ok());
+    assert_eq!(contract_wide.runs(), 0);
+
+    // other functions of the token fall back to the contract-wide policy
+    assert!(check_auth(&protocol, &[&keypair(BOB_SECRET)], call("balance")).is_ok());
+    assert_eq!(contract_wide.runs(), 1);
+
+    // and to the default threshold once no policy is left
+    protocol
+        .client
+        .detach_policy(&vec![env, token.clone()], &vec![env]);
+    protocol.client.set_default_threshold(&2);
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], call("balance"));
+    assert_eq!(invocation, Err(MultiCliqueError::DefaultThresholdNotMet));
+}
+
+#[test]
+fn test_fallback_policy() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let bound = Address::random(env);
+    let fallback = env.register_contract(None, CfoPolicy);
+    let lenient = CountingPolicyClient::new(env, &env.register_contract(None, CountingPolicy));
+    lenient.set_threshold(&1);
+    protocol
+        .client
+        .attach_policy(&lenient.address, &vec![env, bound.clone()], &vec![env]);
+    protocol.client.set_fallback_policy(&fallback);
+    assert_eq!(protocol.client.get_fallback_policy(), Some(fallback));
+
+    // contracts without a binding run through the fallback policy
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], transfer_context(env));
+    assert_eq!(invocation, Err(MultiCliqueError::PolicyThresholdNotMet));
+    assert!(check_auth(&protocol, &[&keypair(ALICE_SECRET)], transfer_context(env)).is_ok());
+
+    // bound contracts don't
+    let context = Context::Contract(ContractContext {
+        contract: bound,
+        fn_name: Symbol::new(env, "transfer"),
+        args: ((), (), 100_i128).into_val(env),
+    });
+    assert!(check_auth(&protocol, &[&keypair(BOB_SECRET)], context).is_ok());
+    assert_eq!(lenient.runs(), 1);
+
+    protocol.client.remove_fallback_policy();
+    assert_eq!(protocol.client.get_fallback_policy(), None);
+    assert!(check_auth(&protocol, &[&keypair(BOB_SECRET)], transfer_context(env)).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "#1001")]
+fn test_remove_fallback_policy_fails_if_not_set() {
+    let Protocol { client, .. } = Protocol::new(2);
+    client.remove_fallback_policy();
+}
+
+#[test]
+fn test_strict_mode() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let bob = keypair(BOB_SECRET);
+    let bound = Address::random(env);
+    let lenient = CountingPolicyClient::new(env, &env.register_contract(None, CountingPolicy));
+    lenient.set_threshold(&1);
+    protocol
+        .client
+        .attach_policy(&lenient.address, &vec![env, bound.clone()], &vec![env]);
+    protocol.client.set_strict_mode(&true);
+    assert!(protocol.client.is_strict_mode());
+
+    let call = |contract: &Address| {
+        Context::Contract(ContractContext {
+            contract: contract.clone(),
+            fn_name: Symbol::new(env, "transfer"),
+            args: ((), (), 100_i128).into_val(env),
+        })
+    };
+
+    // unreviewed contracts are rejected
+    let unknown = Address::random(env);
+    let invocation = check_auth(&protocol, &[&bob], call(&unknown));
+    assert_eq!(invocation, Err(MultiCliqueError::ContractNotAllowed));
+
+    // contracts with a policy, allowlisted contracts and the multiclique itself are not
+    assert!(check_auth(&protocol, &[&bob], call(&bound)).is_ok());
+    assert!(check_auth(&protocol, &[&bob], call(&protocol.protocol_address)).is_ok());
+    protocol.client.allow_contracts(&vec![env, unknown.clone()]);
+    assert_eq!(protocol.client.get_allowlist(), vec![env, unknown.clone()]);
+    assert!(check_auth(&protocol, &[&bob], call(&unknown)).is_ok());
+
+    protocol
+        .client
+        .disallow_contracts(&vec![env, unknown.clone()]);
+    assert_eq!(protocol.client.get_allowlist().len(), 0);
+    let invocation = check_auth(&protocol, &[&bob], call(&unknown));
+    assert_eq!(invocation, Err(MultiCliqueError::ContractNotAllowed));
+
+    protocol.client.set_strict_mode(&false);
+    assert!(check_auth(&protocol, &[&bob], call(&unknown)).is_ok());
+}
+
+fn self_context(protocol: &Protocol) -> Context {
+    let env = &protocol.env;
+    Context::Contract(ContractContext {
+        contract: protocol.protocol_address.clone(),
+        fn_name: Symbol::new(env, "set_default_threshold"),
+        args: (1_u32,).into_val(env),
+    })
+}
+
+#[test]
+fn test_gov_threshold() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let bob = keypair(BOB_SECRET);
+    assert_eq!(protocol.client.get_gov_threshold(), 1);
+    assert!(check_auth(&protocol, &[&bob], self_context(&protocol)).is_ok());
+
+    protocol.client.set_gov_threshold(&2);
+    assert_eq!(protocol.client.get_gov_threshold(), 2);
+    assert_eq!(protocol.client.get_default_threshold(), 1);
+    let invocation = check_auth(&protocol, &[&bob], self_context(&protocol));
+    assert_eq!(invocation, Err(MultiCliqueError::GovThresholdNotMet));
+    let invocation = check_auth(
+        &protocol,
+        &[&bob, &keypair(ALICE_SECRET)],
+        self_context(&protocol),
+    );
+    assert!(invocation.is_ok());
+
+    // day-to-day calls still use the default threshold
+    assert!(check_auth(&protocol, &[&bob], transfer_context(env)).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_gov_threshold_below_default_threshold_fails() {
+    let Protocol { client, .. } = Protocol::new(2);
+    client.set_gov_threshold(&1);
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_gov_threshold_must_not_be_zero() {
+    let Protocol { client, .. } = Protocol::new(1);
+    client.set_default_threshold(&0);
+    client.set_gov_threshold(&0);
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_default_threshold_cannot_exceed_gov_threshold() {
+    let Protocol { client, .. } = Protocol::new(1);
+    client.set_gov_threshold(&1);
+    client.set_default_threshold(&2);
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_signers_cannot_be_removed_below_gov_threshold() {
+    let Protocol { client, env, .. } = Protocol::new(1);
+    client.set_gov_threshold(&2);
+    client.remove_signer(&signer(&env, BOB_SECRET, 1).key);
+}
+
+fn deploy_context(env: &Env, wasm_hash: &BytesN<32>) -> Context {
+    Context::CreateContractHostFn(CreateContractHostFnContext {
+        executable: ContractExecutable::Wasm(wasm_hash.clone()),
+        salt: BytesN::random(env),
+    })
+}
+
+#[test]
+fn test_deploy_threshold() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let wasm_hash = BytesN::random(env);
+    let bob = keypair(BOB_SECRET);
+
+    // defaults to the default threshold
+    assert_eq!(protocol.client.get_deploy_threshold(), 1);
+    assert!(check_auth(&protocol, &[&bob], deploy_context(env, &wasm_hash)).is_ok());
+
+    protocol.client.set_deploy_threshold(&2);
+    let invocation = check_auth(&protocol, &[&bob], deploy_context(env, &wasm_hash));
+    assert_eq!(invocation, Err(MultiCliqueError::DeployThresholdNotMet));
+    let invocation = check_auth(
+        &protocol,
+        &[&bob, &keypair(ALICE_SECRET)],
+        deploy_context(env, &wasm_hash),
+    );
+    assert!(invocation.is_ok());
+}
+
+#[test]
+fn test_deploy_requires_default_cliques() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let wasm_hash = BytesN::random(env);
+    let board = Symbol::new(env, "board");
+    protocol
+        .client
+        .create_clique(&board, &vec![env, signer(env, ALICE_SECRET, 1).key], &1);
+    protocol.client.set_default_cliques(&vec![env, board]);
+
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(BOB_SECRET)],
+        deploy_context(env, &wasm_hash),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::CliqueQuorumNotMet));
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(ALICE_SECRET)],
+        deploy_context(env, &wasm_hash),
+    );
+    assert!(invocation.is_ok());
+}
+
+#[test]
+#[should_panic(expected = "#1008")]
+fn test_deploy_threshold_cannot_exceed_signer_weight() {
+    let Protocol { client, .. } = Protocol::new(1);
+    client.set_deploy_threshold(&3);
+}
+
+/// Test deploy policy that only allows deploying a single wasm hash.
+#[contract]
+struct WasmAllowlistPolicy;
+
+#[contractimpl]
+impl WasmAllowlistPolicy {
+    pub fn allow(env: Env, wasm_hash: BytesN<32>) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "allowed"), &wasm_hash);
+    }
+}
+
+#[contractimpl]
+impl MultiCliqueDeployPolicyTrait for WasmAllowlistPolicy {
+    fn get_deployment_threshold(
+        env: Env,
+        signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
+        wasm_hash: BytesN<32>,
+        _salt: BytesN<32>,
+    ) -> u32 {
+        let allowed: Option<BytesN<32>> =
+            env.storage().instance().get(&Symbol::new(&env, "allowed"));
+        match allowed == Some(wasm_hash) {
+            true => 0,
+            false => signed_weight + 1,
+        }
+    }
+
+    fn run_deployment_policy(
+        _env: Env,
+        _signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
+        _wasm_hash: BytesN<32>,
+        _salt: BytesN<32>,
+    ) {
+    }
+}
+
+#[test]
+fn test_deploy_policy() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let reviewed = BytesN::random(env);
+    let policy =
+        WasmAllowlistPolicyClient::new(env, &env.register_contract(None, WasmAllowlistPolicy));
+    policy.allow(&reviewed);
+    protocol.client.set_deploy_policy(&policy.address);
+    assert_eq!(protocol.client.get_deploy_policy(), Some(policy.address));
+
+    let signers = [&keypair(BOB_SECRET), &keypair(ALICE_SECRET)];
+    assert!(check_auth(&protocol, &signers, deploy_context(env, &reviewed)).is_ok());
+    let invocation = check_auth(
+        &protocol,
+        &signers,
+        deploy_context(env, &BytesN::random(env)),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::DeployThresholdNotMet));
+
+    // the deploy threshold still applies to allowlisted hashes
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(BOB_SECRET)],
+        deploy_context(env, &reviewed),
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::DeployThresholdNotMet));
+
+    protocol.client.remove_deploy_policy();
+    assert_eq!(protocol.client.get_deploy_policy(), None);
+    assert!(check_auth(
+        &protocol,
+        &signers,
+        deploy_context(env, &BytesN::random(env))
+    )
+    .is_ok());
+}
+
+/// A minimal module the host accepts as contract code: just the environment interface version
+/// 20 (pre-release 57) in a `contractenvmetav0` custom section.
+fn empty_contract_wasm() -> [u8; 40] {
+    let mut wasm = [0u8; 40];
+    wasm[..8].copy_from_slice(b"\0asm\x01\0\0\0");
+    wasm[8..11].copy_from_slice(&[0, 30, 17]);
+    wasm[11..28].copy_from_slice(b"contractenvmetav0");
+    wasm[32..].copy_from_slice(&(20_u64 << 32 | 57).to_be_bytes());
+    wasm
+}
+
+/// Test contract deploying contracts on behalf of a deployer address.
+#[contract]
+struct Factory;
+
+#[contractimpl]
+impl Factory {
+    pub fn deploy(env: Env, deployer: Address, salt: BytesN<32>, wasm_hash: BytesN<32>) -> Address {
+        env.deployer()
+            .with_address(deployer, salt)
+            .deploy(wasm_hash)
+    }
+}
+
+/// Deploys a contract with the MultiClique as deployer, authorized by signatures of the given
+/// keys over the payload the host computes for the deployment.
+fn deploy(
+    protocol: &Protocol,
+    keys: &[&Keypair],
+    wasm_hash: &BytesN<32>,
+    salt: &BytesN<32>,
+) -> Address {
+    let env = &protocol.env;
+    let address = ScAddress::try_from(&protocol.protocol_address).unwrap();
+    let nonce = 7;
+    let signature_expiration_ledger = env.ledger().sequence() + 100;
+
+    let invocation = SorobanAuthorizedInvocation {
+        function: SorobanAuthorizedFunction::CreateContractHostFn(CreateContractArgs {
+            contract_id_preimage: ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+                address: address.clone(),
+                salt: Uint256(salt.to_array()),
+            }),
+            executable: xdr::ContractExecutable::Wasm(Hash(wasm_hash.to_array())),
+        }),
+        sub_invocations: Default::default(),
+    };
+    let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+        network_id: Hash(env.ledger().network_id().to_array()),
+        nonce,
+        signature_expiration_ledger,
+        invocation: invocation.clone(),
+    });
+    let payload = env
+        .crypto()
+        .sha256(&Bytes::from_slice(env, &preimage.to_xdr().unwrap()));
+
+    let mut signatures: Vec<Val> = Vec::new(env);
+    for key in keys {
+        signatures.push_back(sign(env, key, &payload));
+    }
+    env.set_auths(&[SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address,
+            nonce,
+            signature_expiration_ledger,
+            signature: ScVal::try_from_val(env, &signatures.to_val()).unwrap(),
+        }),
+        root_invocation: invocation,
+    }]);
+
+    FactoryClient::new(env, &env.register_contract(None, Factory)).deploy(
+        &protocol.protocol_address,
+        salt,
+        wasm_hash,
+    )
+}
+
+#[test]
+fn test_deploy_contract() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let wasm_hash = env.deployer().upload_contract_wasm(empty_contract_wasm());
+    let policy =
+        WasmAllowlistPolicyClient::new(env, &env.register_contract(None, WasmAllowlistPolicy));
+    policy.allow(&wasm_hash);
+    protocol.client.set_deploy_threshold(&2);
+    protocol.client.set_deploy_policy(&policy.address);
+
+    let salt = BytesN::random(env);
+    let deployed = deploy(
+        &protocol,
+        &[&keypair(BOB_SECRET), &keypair(ALICE_SECRET)],
+        &wasm_hash,
+        &salt,
+    );
+    assert_eq!(
+        deployed,
+        env.deployer()
+            .with_address(protocol.protocol_address.clone(), salt)
+            .deployed_address()
+    );
+}
+
+#[test]
+#[should_panic(expected = "#1022")]
+fn test_deploy_contract_fails_below_deploy_threshold() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let wasm_hash = env.deployer().upload_contract_wasm(empty_contract_wasm());
+    protocol.client.set_deploy_threshold(&2);
+
+    deploy(
+        &protocol,
+        &[&keypair(BOB_SECRET)],
+        &wasm_hash,
+        &BytesN::random(env),
+    );
+}
+
+#[test]
+fn test_function_policy_events() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let policy = Address::random(&env);
+    let context = vec![&env, Address::random(&env)];
+    let fn_names = vec![
+        &env,
+        Symbol::new(&env, "transfer"),
+        Symbol::new(&env, "approve"),
+    ];
+    client.attach_policy(&policy, &context, &fn_names);
+    assert_eq!(client.get_policies(&context, &fn_names).len(), 2);
+    assert_eq!(client.get_policies(&context, &vec![&env]).len(), 0);
+
+    let (_, _, data) = env.events().all().last().unwrap();
+    let data: PolicyAddedEventData = data.into_val(&env);
+    assert_eq!(data.fn_names, fn_names);
+
+    client.detach_policy(&context, &vec![&env, Symbol::new(&env, "approve")]);
+    assert_eq!(client.get_policies(&context, &fn_names), vec![&env, policy]);
+    let (_, _, data) = env.events().all().last().unwrap();
+    let data: PolicyRemovedEventData = data.into_val(&env);
+    assert_eq!(data.fn_names, vec![&env, Symbol::new(&env, "approve")]);
+}
+
+// Lives in its own module, as its contract entry points would clash with `CfoPolicy`'s.
+mod counting {
+    use super::*;
+
+    /// Test policy with a configurable threshold that counts how often it has been run.
+    #[contract]
+    pub struct CountingPolicy;
+
+    #[contractimpl]
+    impl CountingPolicy {
+        pub fn set_threshold(env: Env, threshold: u32) {
+            env.storage()
+                .instance()
+                .set(&Symbol::new(&env, "threshold"), &threshold);
+        }
+
+        pub fn runs(env: Env) -> u32 {
+            env.storage()
+                .instance()
+                .get(&Symbol::new(&env, "runs"))
+                .unwrap_or(0)
+        }
+    }
+
+    #[contractimpl]
+    impl MultiCliquePolicyTrait for CountingPolicy {
+        fn get_threshold(
+            env: Env,
+            _signed_weight: u32,
+            _signers: Vec<Signer>,
+            _signed: Vec<SignerKey>,
+            _address: Address,
+            _fn_name: Symbol,
+            _args: Vec<Val>,
+        ) -> u32 {
+            env.storage()
+                .instance()
+                .get(&Symbol::new(&env, "threshold"))
+                .unwrap()
+        }
+
+        fn get_required_cliques(
+            env: Env,
+            _signed_weight: u32,
+            _signers: Vec<Signer>,
+            _signed: Vec<SignerKey>,
+            _address: Address,
+            _fn_name: Symbol,
+            _args: Vec<Val>,
+        ) -> Vec<Symbol> {
+            Vec::new(&env)
+        }
+
+        fn run_policy(
+            env: Env,
+            _signed_weight: u32,
+            _signers: Vec<Signer>,
+            _signed: Vec<SignerKey>,
+            _address: Address,
+            _fn_name: Symbol,
+            _args: Vec<Val>,
+        ) {
+            let runs = Self::runs(env.clone()) + 1;
+            env.storage()
+                .instance()
+                .set(&Symbol::new(&env, "runs"), &runs);
+        }
+    }
+}
+
+use counting::{CountingPolicy, CountingPolicyClient};
+
+#[test]
+fn test_session_key_runs_policies() {
+    let Session {
+        protocol,
+        votes,
+        bot,
+    } = with_session(None);
+    let env = &protocol.env;
+    let policy = CountingPolicyClient::new(env, &env.register_contract(None, CountingPolicy));
+    policy.set_threshold(&5);
+    protocol
+        .client
+        .attach_policy(&policy.address, &vec![env, votes.clone()], &vec![env]);
+
+    // session keys have no weight to meet thresholds with, but the policies still run
+    let invocation = check_session_auth(
+        &protocol,
+        &bot,
+        vec![env, call(env, &votes, "mark_implemented")],
+    );
+    assert!(invocation.is_ok());
+    assert_eq!(policy.runs(), 1);
+}
+
+#[test]
+fn test_session_key_strict_mode() {
+    let Session {
+        protocol,
+        votes,
+        bot,
+    } = with_session(None);
+    let env = &protocol.env;
+    protocol.client.set_strict_mode(&true);
+    let invocation = check_session_auth(
+        &protocol,
+        &bot,
+        vec![env, call(env, &votes, "mark_implemented")],
+    );
+    assert_eq!(invocation, Err(MultiCliqueError::ContractNotAllowed));
+}
+
+#[test]
+fn test_session_key_native_spend_limit() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    let token = Address::random(env);
+    let bot = keypair(EVE_SECRET);
+    protocol.client.add_session_key(
+        &bot.public.to_bytes().into_val(env),
+        &vec![
+            env,
+            SessionScope {
+                contract: token.clone(),
+                fn_name: Symbol::new(env, "transfer"),
+            },
+        ],
+        &(env.ledger().sequence() + 100),
+        &None,
+    );
+    protocol.client.set_spend_limit(&token, &100, &50, &1);
+
+    let transfer = token_call(&protocol, &token, "transfer", 60);
+    assert!(check_session_auth(&protocol, &bot, vec![env, transfer]).is_ok());
+    assert_eq!(protocol.client.get_spent(&token), 60);
+
+    let transfer = token_call(&protocol, &token, "transfer", 50);
+    let invocation = check_session_auth(&protocol, &bot, vec![env, transfer]);
+    assert_eq!(invocation, Err(MultiCliqueError::DefaultThresholdNotMet));
+    assert_eq!(protocol.client.get_spent(&token), 60);
+}
+
+#[test]
+fn test_stacked_policies_highest_threshold_wins() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    let token = Address::random(env);
+    let lenient = CountingPolicyClient::new(env, &env.register_contract(None, CountingPolicy));
+    let strict = CountingPolicyClient::new(env, &env.register_contract(None, CountingPolicy));
+    lenient.set_threshold(&1);
+    strict.set_threshold(&2);
+    protocol
+        .client
+        .attach_policy(&lenient.address, &vec![env, token.clone()], &vec![env]);
+    protocol
+        .client
+        .attach_policy(&strict.address, &vec![env, token.clone()], &vec![env]);
+
+    let context = Context::Contract(ContractContext {
+        contract: token,
+        fn_name: Symbol::new(env, "transfer"),
+        args: ((), (), 100_i128).into_val(env),
+    });
+
+    let invocation = check_auth(&protocol, &[&keypair(BOB_SECRET)], context.clone());
+    assert_eq!(invocation, Err(MultiCliqueError::PolicyThresholdNotMet));
+
+    let invocation = check_auth(
+        &protocol,
+        &[&keypair(BOB_SECRET), &keypair(ALICE_SECRET)],
+        context,
+    );
+    assert!(invocation.is_ok());
+    assert_eq!(lenient.runs(), 1);
+    assert_eq!(strict.runs(), 1);
+}
+
+const PASSKEY_SECRET: [u8; 32] = [7; 32];
+
+fn passkey_signer(e: &Env, weight: u32) -> Signer {
+    let key = SigningKey::from_bytes(&PASSKEY_SECRET.into()).unwrap();
+    let public_key: [u8; 65] = key
+        .verifying_key()
+        .to_encoded_point(false)
+        .as_bytes()
+        .try_into()
+        .unwrap();
+    Signer {
+        key: SignerKey::Secp256r1(BytesN::from_array(e, &public_key)),
+        weight,
+        expires_at: 0,
+    }
+}
+
+fn sign_passkey(e: &Env, challenge: &BytesN<32>) -> Val {
+    sign_passkey_client_data(e, &client_data(e, b"webauthn.get", challenge))
+}
+
+fn client_data(e: &Env, assertion_type: &[u8], challenge: &BytesN<32>) -> Bytes {
+    let mut client_data_json = Bytes::from_slice(e, b"{\"type\":\"");
+    client_data_json.extend_from_slice(assertion_type);
+    client_data_json.extend_from_slice(b"\",\"challenge\":\"");
+    client_data_json.extend_from_array(&base64_url_encode(&challenge.to_array()));
+    client_data_json.extend_from_slice(b"\",\"origin\":\"https://multiclique.app\"}");
+    client_data_json
+}
+
+fn sign_passkey_client_data(e: &Env, client_data_json: &Bytes) -> Val {
+    let key = SigningKey::from_bytes(&PASSKEY_SECRET.into()).unwrap();
+    let public_key = match passkey_signer(e, 1).key {
+        SignerKey::Secp256r1(public_key) => public_key,
+        _ => unreachable!(),
+    };
+
+    let mut authenticator_data = Bytes::from_array(e, &[0; 32]);
+    authenticator_data.extend_from_array(&[0x05, 0, 0, 0, 1]);
+
+    let mut message = authenticator_data.clone();
+    message.append(&e.crypto().sha256(client_data_json).into());
+    let digest = e.crypto().sha256(&message);
+    let signature: P256Signature = key.sign_prehash(&digest.to_array()).unwrap();
+
+    SignedMessage::Secp256r1(WebAuthnSignature {
+        public_key,
+        authenticator_data,
+        client_data_json: client_data_json.clone(),
+        signature: BytesN::from_array(e, &signature.to_bytes().into()),
+    })
+    .into_val(e)
+}
+
+#[test]
+fn test_passkey_signer() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+    protocol.client.add_signer(&passkey_signer(env, 1));
+
+    // ed25519 keys sort before secp256r1 keys
+    let payload = BytesN::random(env);
+    let invocation = env.try_invoke_contract_check_auth::<MultiCliqueError>(
+        &protocol.protocol_address.contract_id(),
+        &payload,
+        &vec![
+            env,
+            sign(env, &keypair(ALICE_SECRET), &payload),
+            sign_passkey(env, &payload),
+        ],
+        &vec![env, transfer_context(env)],
+    );
+    assert!(invocation.is_ok());
+}
+
+#[test]
+fn test_passkey_challenge_mismatch() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    protocol.client.add_signer(&passkey_signer(env, 1));
+
+    let payload = BytesN::random(env);
+    let invocation = env.try_invoke_contract_check_auth::<MultiCliqueError>(
+        &protocol.protocol_address.contract_id(),
+        &payload,
+        &vec![env, sign_passkey(env, &BytesN::random(env))],
+        &vec![env, transfer_context(env)],
+    );
+    assert_eq!(
+        invocation.err().unwrap().unwrap(),
+        MultiCliqueError::ChallengeMismatch
+    );
+}
+
+#[test]
+fn test_passkey_client_data_type() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    protocol.client.add_signer(&passkey_signer(env, 1));
+
+    // a registration must not pass as an assertion
+    let payload = BytesN::random(env);
+    let invocation = env.try_invoke_contract_check_auth::<MultiCliqueError>(
+        &protocol.protocol_address.contract_id(),
+        &payload,
+        &vec![
+            env,
+            sign_passkey_client_data(env, &client_data(env, b"webauthn.create", &payload)),
+        ],
+        &vec![env, transfer_context(env)],
+    );
+    assert_eq!(
+        invocation.err().unwrap().unwrap(),
+        MultiCliqueError::InvalidClientData
+    );
+}
+
+#[test]
+fn test_passkey_client_data_fields() {
+    let protocol = Protocol::new(1);
+    let env = &protocol.env;
+    protocol.client.add_signer(&passkey_signer(env, 1));
+    let payload = BytesN::random(env);
+    let challenge = base64_url_encode(&payload.to_array());
+    let check = |json: &[&[u8]]| {
+        let mut client_data_json = Bytes::new(env);
+        for part in json {
+            client_data_json.extend_from_slice(part);
+        }
+        env.try_invoke_contract_check_auth::<MultiCliqueError>(
+            &protocol.protocol_address.contract_id(),
+            &payload,
+            &vec![env, sign_passkey_client_data(env, &client_data_json)],
+            &vec![env, transfer_context(env)],
+        )
+    };
+
+    // member order, whitespace and unknown members don't matter
+    assert!(check(&[
+        b"{ \"origin\": \"https://multiclique.app\", \"crossOrigin\": false,\n",
+        b"  \"tokenBinding\": {\"status\": \"present\"}, \"challenge\": \"",
+        &challenge,
+        b"\", \"type\": \"webauthn.get\" }",
+    ])
+    .is_ok());
+
+    // the payload only counts as the challenge member of the top level object
+    assert_eq!(
+        check(&[
+            b"{\"type\":\"webauthn.get\",\"challenge\":\"AAAA\",\"other\":{\"challenge\":\"",
+            &challenge,
+            b"\"}}",
+        ])
+        .err()
+        .unwrap()
+        .unwrap(),
+        MultiCliqueError::ChallengeMismatch
+    );
+    assert_eq!(
+        check(&[
+            b"{\"type\":\"webauthn.get\",\"origin\":\"\\\"challenge\\\":\\\"",
+            &challenge,
+            b"\\\"\"}",
+        ])
+        .err()
+        .unwrap()
+        .unwrap(),
+        MultiCliqueError::InvalidClientData
+    );
+
+    // duplicate members, escapes and malformed JSON are rejected
+    for json in [
+        &[
+            b"{\"type\":\"webauthn.get\",\"challenge\":\"AAAA\",\"challenge\":\"".as_slice(),
+            &challenge,
+            b"\"}",
+        ][..],
+        &[
+            b"{\"type\":\"webauthn\\u002eget\",\"challenge\":\"",
+            &challenge,
+            b"\"}",
+        ],
+        &[
+            b"{\"type\":\"webauthn.get\",\"challenge\":\"",
+            &challenge,
+            b"\"",
+        ],
+        &[
+            b"{\"type\":\"webauthn.get\",\"challenge\":\"",
+            &challenge,
+            b"\"}x",
+        ],
+        &[
+            b"{\"type\":\"webauthn.get\" \"challenge\":\"",
+            &challenge,
+            b"\"}",
+        ],
+    ] {
+        assert_eq!(
+            check(json).err().unwrap().unwrap(),
+            MultiCliqueError::InvalidClientData
+        );
+    }
+}
+
+#[test]
+fn test_address_signer() {
+    let protocol = Protocol::new(2);
+    let env = &protocol.env;
+
+    // a nested multiclique acting as a single signer
+    let department = env.register_contract(None, Contract);
+    protocol.client.add_signer(&Signer {
+        key: SignerKey::Address(department.clone()),
+        weight: 1,
+        expires_at: 0,
+    });
+
+    let payload = BytesN::random(env);
+    let invocation = env.try_invoke_contract_check_auth::<MultiCliqueError>(
+        &protocol.protocol_address.contract_id(),
+        &payload,
+        &vec![
+            env,
+            sign(env, &keypair(ALICE_SECRET), &payload),
+            SignedMessage::Address(department.clone()).into_val(env),
+        ],
+        &vec![env, transfer_context(env)],
+    );
+    assert!(invocation.is_ok());
+    assert_eq!(env.auths()[0].0, department);
+}
+
+#[test]
+fn test_remove_address_signer() {
+    let Protocol { client, env, .. } = Protocol::new(2);
+    let department = SignerKey::Address(Address::random(&env));
+    client.add_signer(&Signer {
+        key: department.clone(),
+        weight: 1,
+        expires_at: 0,
+    });
+    assert!(client
+        .get_signers()
+        .iter()
+        .any(|signer| signer.key == department));
+    client.remove_signer(&department);
+    assert_eq!(client.get_signers().len(), 2);
+    assert_eq!(env.events().all().len(), 3);
+}
+
+/// Returns the last ledger in which the instance of a contract is live.
+fn instance_expiration(env: &Env, contract: &Address) -> u32 {
+    let contract = ScAddress::Contract(Hash(contract.contract_id().to_array()));
+    env.to_snapshot()
+        .ledger_entries
+        .into_iter()
+        .find_map(|(key, (_, expiration))| match *key {
+            LedgerKey::ContractData(data)
+                if data.contract == contract && data.key == ScVal::LedgerKeyContractInstance =>
+            {
+                expiration
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_instance_outlives_default_expiration() {
+    let Protocol {
+        client,
+        env,
+        protocol_address,
+        ..
+    } = Protocol::new(2);
+    let expiration = env.ledger().sequence() + BUMP_A_YEAR - 1;
+    assert_eq!(instance_expiration(&env, &protocol_address), expiration);
+
+    // well past the minimum lifetime of a fresh instance, but not yet due for a bump
+    env.ledger().with_mut(|li| li.sequence_number += 100_000);
+    client.extend_ttl();
+    assert_eq!(instance_expiration(&env, &protocol_address), expiration);
+    assert_eq!(client.get_signers().len(), 2);
+}
+
+#[test]
+fn test_state_changes_extend_ttl() {
+    let Protocol {
+        client,
+        env,
+        protocol_address,
+        ..
+    } = Protocol::new(2);
+    env.ledger()
+        .with_mut(|li| li.sequence_number += BUMP_THRESHOLD + 1);
+    client.add_signer(&signer(&env, EVE_SECRET, 1));
+    assert_eq!(
+        instance_expiration(&env, &protocol_address),
+        env.ledger().sequence() + BUMP_A_YEAR - 1
+    );
+
+    env.ledger()
+        .with_mut(|li| li.sequence_number += BUMP_THRESHOLD + 1);
+    client.extend_ttl();
+    assert_eq!(
+        instance_expiration(&env, &protocol_address),
+        env.ledger().sequence() + BUMP_A_YEAR - 1
+    );
+    assert_eq!(client.get_signers().len(), 3);
+}
+
+#[test]
+fn test_version_set_on_init() {
+    let Protocol { client, .. } = Protocol::new(2);
+    assert_eq!(client.version(), VERSION);
+}
+
+#[test]
+fn test_migrate_legacy_signers() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+    let protocol_address = env.register_contract(None, Contract);
+    let client = ContractClient::new(&env, &protocol_address);
+    let alice: BytesN<32> = keypair(ALICE_SECRET).public.to_bytes().into_val(&env);
+    let bob: BytesN<32> = keypair(BOB_SECRET).public.to_bytes().into_val(&env);
+
+    // storage as written by the first release
+    env.as_contract(&protocol_address, || {
+        env.storage()
+            .instance()
+            .set(&DataKey::Signers, &vec![&env, alice.clone(), bob.clone()]);
+        env.storage()
+            .instance()
+            .set(&DataKey::DefaultThreshold, &2_u32);
+    });
+    assert_eq!(client.version(), 1);
+
+    client.migrate();
+    assert_eq!(client.version(), VERSION);
+    assert_eq!(
+        client.get_signers(),
+        vec![
+            &env,
+            Signer {
+                key: SignerKey::Ed25519(alice),
+                weight: 1,
+                expires_at: 0,
+            },
+            Signer {
+                key: SignerKey::Ed25519(bob),
+                weight: 1,
+                expires_at: 0,
+            },
+        ]
+    );
+    assert_eq!(env.events().all().len(), 1);
+
+    // migrating again is a no-op
+    client.migrate();
+    assert_eq!(env.events().all().len(), 1);
+}
+
+#[test]
+fn test_migrate_signers_without_expiry() {
+    let Protocol {
+        client,
+        env,
+        protocol_address,
+        ..
+    } = Protocol::new(2);
+
+    // storage as written by version 3
+    env.as_contract(&protocol_address, || {
+        let legacy = vec![
+            &env,
+            SignerV2 {
+                key: signer(&env, ALICE_SECRET, 1).key,
+                weight: 2,
+            },
+        ];
+        env.storage().instance().set(&DataKey::Signers, &legacy);
+        env.storage().instance().set(&DataKey::Version, &3_u32);
+    });
+
+    client.migrate();
+    assert_eq!(client.version(), VERSION);
+    assert_eq!(
+        client.get_signers(),
+        vec![&env, signer(&env, ALICE_SECRET, 2)]
+    );
+}
+
+#[test]
+fn test_upgrade() {
+    let Protocol {
+        client,
+        env,
+        protocol_address,
+        ..
+    } = Protocol::new(2);
+    let wasm_hash = env
+        .deployer()
+        .upload_contract_wasm(include_bytes!("../../../wasm/multiclique.wasm").as_slice());
+    client.upgrade(&wasm_hash);
+    assert_eq!(env.auths()[0].0, protocol_address);
+    assert_eq!(env.events().all().len(), 2);
 }
@@ -0,0 +1,212 @@
+use crate::errors::MultiCliqueError;
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use soroban_sdk::{panic_with_error, Bytes, BytesN, Env};
+
+/// Upper bound for the client data JSON we are willing to inspect.
+const MAX_CLIENT_DATA_LEN: usize = 1024;
+
+/// Minimum length of the authenticator data: rp id hash (32), flags (1) and sign counter (4).
+const MIN_AUTHENTICATOR_DATA_LEN: u32 = 37;
+
+/// The "user present" flag in the authenticator data.
+const USER_PRESENT: u8 = 0x01;
+
+/// The `type` of the client data of an assertion.
+const ASSERTION_TYPE: &[u8] = b"webauthn.get";
+
+const BASE64_URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Verifies a WebAuthn assertion of a secp256r1 passkey over the given signature payload.
+///
+/// The client data has to be of type `webauthn.get` with the payload as its challenge, the user
+/// has to be present and the signature has to be valid over
+/// `authenticator_data || sha256(client_data_json)`.
+pub fn verify(
+    env: &Env,
+    public_key: &BytesN<65>,
+    signature_payload: &BytesN<32>,
+    authenticator_data: &Bytes,
+    client_data_json: &Bytes,
+    signature: &BytesN<64>,
+) {
+    if authenticator_data.len() < MIN_AUTHENTICATOR_DATA_LEN
+        || authenticator_data.get_unchecked(32) & USER_PRESENT == 0
+    {
+        panic_with_error!(env, MultiCliqueError::InvalidSignature);
+    }
+
+    let len = client_data_json.len() as usize;
+    if len > MAX_CLIENT_DATA_LEN {
+        panic_with_error!(env, MultiCliqueError::InvalidClientData);
+    }
+    let mut json = [0u8; MAX_CLIENT_DATA_LEN];
+    client_data_json.copy_into_slice(&mut json[..len]);
+    let (assertion_type, challenge) = match client_data_fields(&json[..len]) {
+        Some(fields) => fields,
+        None => panic_with_error!(env, MultiCliqueError::InvalidClientData),
+    };
+    if assertion_type != ASSERTION_TYPE {
+        panic_with_error!(env, MultiCliqueError::InvalidClientData);
+    }
+    if challenge != base64_url_encode(&signature_payload.to_array()) {
+        panic_with_error!(env, MultiCliqueError::ChallengeMismatch);
+    }
+
+    let mut message = authenticator_data.clone();
+    message.append(&env.crypto().sha256(client_data_json).into());
+    let digest = env.crypto().sha256(&message);
+
+    let verifying_key = match VerifyingKey::from_sec1_bytes(&public_key.to_array()) {
+        Ok(key) => key,
+        Err(_) => panic_with_error!(env, MultiCliqueError::InvalidSignature),
+    };
+    let signature = match Signature::from_slice(&signature.to_array()) {
+        Ok(signature) => signature,
+        Err(_) => panic_with_error!(env, MultiCliqueError::InvalidSignature),
+    };
+    if verifying_key
+        .verify_prehash(&digest.to_array(), &signature)
+        .is_err()
+    {
+        panic_with_error!(env, MultiCliqueError::InvalidSignature);
+    }
+}
+
+/// Returns the raw `type` and `challenge` of the client data JSON.
+///
+/// Only the members of the top level object are considered, so neither field can be smuggled in
+/// through a nested value or a string. Both have to be plain strings without escapes and must
+/// occur exactly once; anything else, as well as malformed JSON, yields `None`.
+fn client_data_fields(json: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut assertion_type = None;
+    let mut challenge = None;
+
+    let mut pos = skip_whitespace(json, 0);
+    if json.get(pos) != Some(&b'{') {
+        return None;
+    }
+    pos = skip_whitespace(json, pos + 1);
+    if json.get(pos) == Some(&b'}') {
+        return None;
+    }
+    loop {
+        let key_end = string_end(json, pos)?;
+        let key = &json[pos + 1..key_end - 1];
+        pos = skip_whitespace(json, key_end);
+        if json.get(pos) != Some(&b':') {
+            return None;
+        }
+        pos = skip_whitespace(json, pos + 1);
+
+        let value_end = skip_value(json, pos)?;
+        let field = match key {
+            b"type" => Some(&mut assertion_type),
+            b"challenge" => Some(&mut challenge),
+            _ => None,
+        };
+        if let Some(field) = field {
+            let value = json.get(pos + 1..value_end.checked_sub(1)?)?;
+            if json[pos] != b'"' || value.contains(&b'\\') || field.is_some() {
+                return None;
+            }
+            *field = Some(value);
+        }
+
+        pos = skip_whitespace(json, value_end);
+        match json.get(pos) {
+            Some(b',') => pos = skip_whitespace(json, pos + 1),
+            Some(b'}') => break,
+            _ => return None,
+        }
+    }
+    if skip_whitespace(json, pos + 1) != json.len() {
+        return None;
+    }
+    Some((assertion_type?, challenge?))
+}
+
+/// Returns the position after the JSON value starting at `pos`.
+fn skip_value(json: &[u8], pos: usize) -> Option<usize> {
+    match *json.get(pos)? {
+        b'"' => string_end(json, pos),
+        b'{' | b'[' => {
+            let mut depth = 0;
+            let mut pos = pos;
+            loop {
+                match *json.get(pos)? {
+                    b'"' => {
+                        pos = string_end(json, pos)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(pos + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                pos += 1;
+            }
+        }
+        // numbers, booleans and null
+        _ => {
+            let end = pos
+                + json[pos..]
+                    .iter()
+                    .take_while(|byte| !matches!(byte, b',' | b'}' | b']') && !is_whitespace(byte))
+                    .count();
+            (end > pos).then_some(end)
+        }
+    }
+}
+
+/// Returns the position after the JSON string starting at `pos`.
+fn string_end(json: &[u8], pos: usize) -> Option<usize> {
+    if json.get(pos) != Some(&b'"') {
+        return None;
+    }
+    let mut pos = pos + 1;
+    loop {
+        match *json.get(pos)? {
+            b'"' => return Some(pos + 1),
+            b'\\' => pos += 2,
+            _ => pos += 1,
+        }
+    }
+}
+
+fn skip_whitespace(json: &[u8], pos: usize) -> usize {
+    pos + json
+        .get(pos..)
+        .unwrap_or_default()
+        .iter()
+        .take_while(|byte| is_whitespace(byte))
+        .count()
+}
+
+fn is_whitespace(byte: &u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// Encodes 32 bytes as unpadded base64url, as WebAuthn does for the challenge.
+pub(crate) fn base64_url_encode(input: &[u8; 32]) -> [u8; 43] {
+    let mut output = [0u8; 43];
+    let mut out = 0;
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..chunk.len() + 1 {
+            output[out] = BASE64_URL_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize];
+            out += 1;
+        }
+    }
+    output
+}
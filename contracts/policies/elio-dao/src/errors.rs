@@ -10,4 +10,19 @@ pub enum PolicyError {
     /// Error when the spend limit is exceeded.
     /// May occur in `run_asset_policy` function.
     SpendLimitExceeded = 1101,
+    /// Error when a rule requires more than 100% of the signers.
+    /// May occur in `set_rule` function.
+    InvalidRule = 1102,
+    /// Error when there is no rule to remove.
+    /// May occur in `remove_rule` function.
+    RuleDoesNotExist = 1103,
+    /// Error when a spend limit is negative.
+    /// May occur in `set_spend_limit` function.
+    InvalidSpendLimit = 1104,
+    /// Error when a token call carries a negative amount.
+    /// May occur in `run_asset_policy` function.
+    InvalidAmount = 1105,
+    /// Error when the amount spent would overflow.
+    /// May occur in `run_asset_policy` function.
+    AmountOverflow = 1106,
 }
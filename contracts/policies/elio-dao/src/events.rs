@@ -1,3 +1,4 @@
+use crate::Rules;
 use soroban_sdk::{contracttype, symbol_short, Address, Symbol};
 
 // Symbol representing policy-related events.
@@ -15,6 +16,12 @@ pub const SPEND_LIMIT_RESET: Symbol = symbol_short!("lmt_reset");
 // Symbol representing an already spent amount update
 pub const ALREADY_SPENT_UPDATE: Symbol = symbol_short!("spent_upd");
 
+// Symbol representing a rule set event
+pub const RULE_SET: Symbol = symbol_short!("rule_set");
+
+// Symbol representing a rule removed event
+pub const RULE_REMOVED: Symbol = symbol_short!("rule_rmvd");
+
 // Event data for when a policy contract is initiated
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -31,6 +38,7 @@ pub struct PolicyInitEventData {
 pub struct PolicySpendLimitSetEventData {
     pub address: Address,
     pub limit: i128,
+    pub period: u32,
 }
 
 // Event data for when a spend limit is reset
@@ -46,4 +54,21 @@ pub struct PolicySpendLimitResetEventData {
 pub struct PolicyAlreadySpentUpdateEventData {
     pub address: Address,
     pub already_spent: i128,
+    pub window_end: u32,
+}
+
+// Event data for when a rule is set, carrying the resulting rules of the target
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolicyRuleSetEventData {
+    pub address: Address,
+    pub rules: Rules,
+}
+
+// Event data for when a rule is removed, carrying the resulting rules of the target
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolicyRuleRemovedEventData {
+    pub address: Address,
+    pub rules: Rules,
 }
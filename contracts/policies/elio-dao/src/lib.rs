@@ -7,13 +7,14 @@ mod events;
 mod test;
 
 use crate::events::{
-    PolicyAlreadySpentUpdateEventData, PolicyInitEventData, PolicySpendLimitResetEventData,
-    PolicySpendLimitSetEventData, ALREADY_SPENT_UPDATE, INIT, POLICY, SPEND_LIMIT_RESET,
-    SPEND_LIMIT_SET,
+    PolicyAlreadySpentUpdateEventData, PolicyInitEventData, PolicyRuleRemovedEventData,
+    PolicyRuleSetEventData, PolicySpendLimitResetEventData, PolicySpendLimitSetEventData,
+    ALREADY_SPENT_UPDATE, INIT, POLICY, RULE_REMOVED, RULE_SET, SPEND_LIMIT_RESET, SPEND_LIMIT_SET,
 };
 use commons::traits::MultiCliquePolicyTrait;
+use commons::types::{Signer, SignerKey};
 use soroban_sdk::{
-    contract, contractimpl, contracttype, panic_with_error, Address, BytesN, Env, Symbol,
+    contract, contractimpl, contracttype, map, panic_with_error, Address, Env, Map, Symbol,
     TryIntoVal, Val, Vec,
 };
 
@@ -24,20 +25,58 @@ use soroban_sdk::{
 #[contract]
 pub struct Contract;
 
+pub const BUMP_A_YEAR: u32 = 6312000;
+pub const BUMP_THRESHOLD: u32 = BUMP_A_YEAR / 2;
+
+/// Share of signers required for contracts without rules, in percent.
+pub const DEFAULT_PERCENT: u32 = 100;
+/// Share of signers required for tokens with a spend limit and without rules, in percent.
+pub const SPEND_LIMIT_PERCENT: u32 = 50;
+
 #[contracttype]
 #[derive(Clone)]
 enum DataKey {
     MultiClique,
-    Core,
-    Votes,
-    Asset,
     SpendLimit(Address),
     AlreadySpent(Address),
+    Rules(Address),
+}
+
+/// A spend limit for a token, see `set_spend_limit`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpendLimit {
+    /// The amount that can be spent per window.
+    pub limit: i128,
+    /// The length of a window in ledgers, 0 for a window that only ends with `reset_spend_limit`.
+    pub period: u32,
+}
+
+/// The amount spent of a spend limit in the current window.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AlreadySpent {
+    pub amount: i128,
+    /// The ledger the current window started at.
+    pub window_start: u32,
+    /// The first ledger after the current window, `u32::MAX` if it does not end on its own.
+    pub window_end: u32,
+}
+
+/// Threshold rules for one target contract. All values are percentages of the total signer weight.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rules {
+    /// Share required for functions without a rule of their own.
+    pub default: u32,
+    /// Share required per function name.
+    pub functions: Map<Symbol, u32>,
 }
 
 trait ElioDaoPolicyTrait {
     /// ## Init
-    /// Initializes the contract by setting the addresses for MultiClique, Core, Votes, and Asset.
+    /// Initializes the contract by setting the MultiClique address and seeding the default rules
+    /// for the Elio DAO Core, Votes, and Asset contracts.
     ///
     /// - `env`: Environment context.
     /// - `multiclique_address`: Address of the MultiClique protocol
@@ -55,15 +94,19 @@ trait ElioDaoPolicyTrait {
     /// ## Set Spend Limit
     ///
     /// Sets the spend limit for a given token address (expects the soroban token interface).
+    /// The spent amount is reset automatically every `period` ledgers. Windows are aligned to the
+    /// ledger the first window started at, so a late spend does not shift the following windows.
     ///
     /// - `env`: Environment context.
     /// - `address`: Target address.
-    /// - `limit`: Spend limit to set.
-    fn set_spend_limit(env: Env, address: Address, limit: i128);
+    /// - `limit`: Spend limit to set, must not be negative.
+    /// - `period`: Length of a window in ledgers, 0 to only reset with `reset_spend_limit`.
+    fn set_spend_limit(env: Env, address: Address, limit: i128, period: u32);
 
     /// ## Reset Spend Limit
     ///
-    /// Resets the spend limit for a given address to zero (expects the soroban token interface).
+    /// Resets the amount spent in the current window for a given address to zero
+    /// (expects the soroban token interface).
     ///
     /// - `env`: Environment context.
     /// - `address`: Target address.
@@ -71,19 +114,58 @@ trait ElioDaoPolicyTrait {
 
     /// ## Get Spend Limit
     ///
-    /// Returns the spend limit for a given address.
+    /// Returns the spend limit for a given address, if any.
     ///
     /// - `env`: Environment context.
     /// - `address`: Target address.
-    fn get_spend_limit(env: Env, address: Address) -> i128;
+    fn get_spend_limit(env: Env, address: Address) -> Option<SpendLimit>;
 
     /// ## Get Already Spend
     ///
-    /// Returns the amount already spent for a given address.
+    /// Returns the amount already spent in the current window for a given address,
+    /// together with the window's bounds.
     ///
     /// - `env`: Environment context.
     /// - `address`: Target address.
-    fn get_already_spent(env: Env, address: Address) -> i128;
+    fn get_already_spent(env: Env, address: Address) -> AlreadySpent;
+
+    /// ## Set Rule
+    ///
+    /// Sets the share of signers required to call a function on a target contract,
+    /// or the default share for all its functions without a rule of their own.
+    ///
+    /// - `env`: Environment context.
+    /// - `address`: Target contract.
+    /// - `fn_name`: Function name, or `None` to set the default of the target.
+    /// - `percent`: Required share in percent of the total signer weight (0 to 100).
+    ///   0 lets any single signer pass.
+    fn set_rule(env: Env, address: Address, fn_name: Option<Symbol>, percent: u32);
+
+    /// ## Remove Rule
+    ///
+    /// Removes the rule for a function on a target contract, or resets the default of the target
+    /// to `DEFAULT_PERCENT` when `fn_name` is `None`.
+    ///
+    /// - `env`: Environment context.
+    /// - `address`: Target contract.
+    /// - `fn_name`: Function name, or `None` to reset the default of the target.
+    fn remove_rule(env: Env, address: Address, fn_name: Option<Symbol>);
+
+    /// ## Get Rules
+    ///
+    /// Returns the rules for a target contract, if any.
+    ///
+    /// - `env`: Environment context.
+    /// - `address`: Target contract.
+    fn get_rules(env: Env, address: Address) -> Option<Rules>;
+
+    /// ## Extend TTL
+    ///
+    /// Extends the lifetime of the contract's storage, so an idle policy does not get archived.
+    /// Anyone may call this.
+    ///
+    /// - `env`: Environment context.
+    fn extend_ttl(env: Env);
 }
 
 #[contractimpl]
@@ -102,13 +184,43 @@ impl ElioDaoPolicyTrait for Contract {
         env.storage()
             .instance()
             .set(&DataKey::MultiClique, &multiclique_address);
-        env.storage().instance().set(&DataKey::Core, &core_address);
-        env.storage()
-            .instance()
-            .set(&DataKey::Votes, &votes_address);
-        env.storage()
-            .instance()
-            .set(&DataKey::Asset, &asset_address);
+        set_rules(
+            &env,
+            &core_address,
+            &Rules {
+                default: 66,
+                functions: map![
+                    &env,
+                    (Symbol::new(&env, "destroy_dao"), 80),
+                    (Symbol::new(&env, "change_owner"), 80)
+                ],
+            },
+        );
+        set_rules(
+            &env,
+            &votes_address,
+            &Rules {
+                default: 66,
+                functions: map![
+                    &env,
+                    (Symbol::new(&env, "fault_proposal"), 0),
+                    (Symbol::new(&env, "mark_implemented"), 50)
+                ],
+            },
+        );
+        set_rules(
+            &env,
+            &asset_address,
+            &Rules {
+                default: 50,
+                functions: map![
+                    &env,
+                    (Symbol::new(&env, "set_owner"), 80),
+                    (Symbol::new(&env, "set_core_address"), 80)
+                ],
+            },
+        );
+        bump_instance(&env);
 
         env.events().publish(
             (POLICY, INIT),
@@ -122,17 +234,30 @@ impl ElioDaoPolicyTrait for Contract {
     }
 
     // see: ElioDaoPolicyTrait
-    fn set_spend_limit(env: Env, address: Address, limit: i128) {
+    fn set_spend_limit(env: Env, address: Address, limit: i128, period: u32) {
         let contract_address: Address =
             env.storage().instance().get(&DataKey::MultiClique).unwrap();
         contract_address.require_auth();
+        bump_instance(&env);
+        if limit < 0 {
+            panic_with_error!(&env, errors::PolicyError::InvalidSpendLimit);
+        }
+        let spend_limit = SpendLimit { limit, period };
         env.storage()
             .instance()
-            .set(&DataKey::SpendLimit(address.clone()), &limit);
+            .set(&DataKey::SpendLimit(address.clone()), &spend_limit);
+        let spent = current_spent(&env, &address, &spend_limit);
+        env.storage()
+            .instance()
+            .set(&DataKey::AlreadySpent(address.clone()), &spent);
 
         env.events().publish(
             (POLICY, SPEND_LIMIT_SET),
-            PolicySpendLimitSetEventData { address, limit },
+            PolicySpendLimitSetEventData {
+                address,
+                limit,
+                period,
+            },
         );
     }
 
@@ -141,9 +266,12 @@ impl ElioDaoPolicyTrait for Contract {
         let contract_address: Address =
             env.storage().instance().get(&DataKey::MultiClique).unwrap();
         contract_address.require_auth();
+        bump_instance(&env);
+        let mut spent = current_spent(&env, &address, &get_spend_limit(&env, &address));
+        spent.amount = 0;
         env.storage()
             .instance()
-            .set(&DataKey::AlreadySpent(address.clone()), &0_i128);
+            .set(&DataKey::AlreadySpent(address.clone()), &spent);
 
         env.events().publish(
             (POLICY, SPEND_LIMIT_RESET),
@@ -152,19 +280,80 @@ impl ElioDaoPolicyTrait for Contract {
     }
 
     // see: ElioDaoPolicyTrait
-    fn get_spend_limit(env: Env, address: Address) -> i128 {
-        env.storage()
-            .instance()
-            .get(&DataKey::SpendLimit(address))
-            .unwrap_or(0_i128)
+    fn get_spend_limit(env: Env, address: Address) -> Option<SpendLimit> {
+        env.storage().instance().get(&DataKey::SpendLimit(address))
     }
 
     // see: ElioDaoPolicyTrait
-    fn get_already_spent(env: Env, address: Address) -> i128 {
-        env.storage()
-            .instance()
-            .get(&DataKey::AlreadySpent(address))
-            .unwrap_or(0_i128)
+    fn get_already_spent(env: Env, address: Address) -> AlreadySpent {
+        current_spent(&env, &address, &get_spend_limit(&env, &address))
+    }
+
+    // see: ElioDaoPolicyTrait
+    fn set_rule(env: Env, address: Address, fn_name: Option<Symbol>, percent: u32) {
+        let contract_address: Address =
+            env.storage().instance().get(&DataKey::MultiClique).unwrap();
+        contract_address.require_auth();
+        bump_instance(&env);
+        if percent > 100 {
+            panic_with_error!(&env, errors::PolicyError::InvalidRule);
+        }
+
+        let mut rules = get_rules(&env, &address).unwrap_or(Rules {
+            default: DEFAULT_PERCENT,
+            functions: Map::new(&env),
+        });
+        match fn_name {
+            Some(fn_name) => rules.functions.set(fn_name, percent),
+            None => rules.default = percent,
+        }
+        set_rules(&env, &address, &rules);
+
+        env.events().publish(
+            (POLICY, RULE_SET),
+            PolicyRuleSetEventData { address, rules },
+        );
+    }
+
+    // see: ElioDaoPolicyTrait
+    fn remove_rule(env: Env, address: Address, fn_name: Option<Symbol>) {
+        let contract_address: Address =
+            env.storage().instance().get(&DataKey::MultiClique).unwrap();
+        contract_address.require_auth();
+        bump_instance(&env);
+
+        let mut rules = get_rules(&env, &address)
+            .unwrap_or_else(|| panic_with_error!(&env, errors::PolicyError::RuleDoesNotExist));
+        match fn_name {
+            Some(fn_name) => {
+                if rules.functions.remove(fn_name).is_none() {
+                    panic_with_error!(&env, errors::PolicyError::RuleDoesNotExist);
+                }
+            }
+            None => rules.default = DEFAULT_PERCENT,
+        }
+        if rules.default == DEFAULT_PERCENT && rules.functions.is_empty() {
+            env.storage()
+                .instance()
+                .remove(&DataKey::Rules(address.clone()));
+        } else {
+            set_rules(&env, &address, &rules);
+        }
+
+        env.events().publish(
+            (POLICY, RULE_REMOVED),
+            PolicyRuleRemovedEventData { address, rules },
+        );
+    }
+
+    // see: ElioDaoPolicyTrait
+    fn get_rules(env: Env, address: Address) -> Option<Rules> {
+        get_rules(&env, &address)
+    }
+
+    // see: ElioDaoPolicyTrait
+    fn extend_ttl(env: Env) {
+        bump_instance(&env);
     }
 }
 
@@ -173,10 +362,14 @@ impl MultiCliquePolicyTrait for Contract {
     /// ## Get Threshold
     ///
     /// Returns the threshold required for a particular action in the DAO.
+    /// Thresholds are looked up in the rule table of the target contract (see `set_rule`) and are
+    /// percentages of the total weight of all signers, not of the weight that signed. They are
+    /// always rounded up (see `percent_of`).
     ///
     /// - `env`: Environment context.
-    /// - `num_signers`: Number of signers involved in the action.
-    /// - `signers`: List of signers' addresses.
+    /// - `signed_weight`: Summed weight of the signers involved in the action.
+    /// - `signers`: List of signers and their weights.
+    /// - `signed`: Keys of the signers that signed the action.
     /// - `address`: Target address for the action.
     /// - `fn_name`: Function name representing the action.
     /// - `args`: Additional arguments for the action.
@@ -184,34 +377,51 @@ impl MultiCliquePolicyTrait for Contract {
     /// **Returns**: Threshold as a `u32`.
     fn get_threshold(
         env: Env,
-        num_signers: u32,
-        signers: Vec<BytesN<32>>,
+        _signed_weight: u32,
+        signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
         address: Address,
         fn_name: Symbol,
-        args: Vec<Val>,
+        _args: Vec<Val>,
     ) -> u32 {
-        if num_signers < 2 {
-            return 1;
-        }
-        match address {
-            addr if addr == env.storage().instance().get(&DataKey::Core).unwrap() => {
-                get_core_threshold(&env, &num_signers, &signers, &fn_name, &args)
-            }
-            addr if addr == env.storage().instance().get(&DataKey::Votes).unwrap() => {
-                get_votes_threshold(&env, &num_signers, &signers, &fn_name, &args)
-            }
-            addr if addr == env.storage().instance().get(&DataKey::Asset).unwrap() => {
-                get_asset_threshold(&env, &num_signers, &signers, &fn_name, &args)
-            }
-            _ if env
+        let percent = match get_rules(&env, &address) {
+            Some(rules) => rules.functions.get(fn_name).unwrap_or(rules.default),
+            None if env
                 .storage()
                 .instance()
                 .has(&DataKey::SpendLimit(address.clone())) =>
             {
-                (num_signers * 50) / 100
+                SPEND_LIMIT_PERCENT
             }
-            _ => num_signers,
-        }
+            None => DEFAULT_PERCENT,
+        };
+        percent_of(total_weight(&signers), percent)
+    }
+
+    /// ## Get Required Cliques
+    ///
+    /// Returns the cliques that have to reach their quorum for a particular action in the DAO.
+    /// The Elio DAO preset works on plain thresholds and does not require any cliques.
+    ///
+    /// - `env`: Environment context.
+    /// - `signed_weight`: Summed weight of the signers involved in the action.
+    /// - `signers`: List of signers and their weights.
+    /// - `signed`: Keys of the signers that signed the action.
+    /// - `address`: Target address for the action.
+    /// - `fn_name`: Function name representing the action.
+    /// - `args`: Additional arguments for the action.
+    ///
+    /// **Returns**: An empty list of clique names.
+    fn get_required_cliques(
+        env: Env,
+        _signed_weight: u32,
+        _signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
+        _address: Address,
+        _fn_name: Symbol,
+        _args: Vec<Val>,
+    ) -> Vec<Symbol> {
+        Vec::new(&env)
     }
 
     /// ## Run Policy
@@ -219,104 +429,144 @@ impl MultiCliquePolicyTrait for Contract {
     /// Executes the policy rules based on the given action and parameters.
     ///
     /// - `env`: Environment context.
-    /// - `num_signers`: Number of signers involved in the action.
-    /// - `signers`: List of signers' addresses.
+    /// - `signed_weight`: Summed weight of the signers involved in the action.
+    /// - `signers`: List of signers and their weights.
+    /// - `signed`: Keys of the signers that signed the action.
     /// - `address`: Target address for the action.
     /// - `fn_name`: Function name representing the action.
     /// - `args`: Additional arguments for the action.
     fn run_policy(
         env: Env,
-        num_signers: u32,
-        signers: Vec<BytesN<32>>,
+        signed_weight: u32,
+        signers: Vec<Signer>,
+        _signed: Vec<SignerKey>,
         address: Address,
         fn_name: Symbol,
         args: Vec<Val>,
     ) {
+        bump_instance(&env);
         if env
             .storage()
             .instance()
             .has(&DataKey::SpendLimit(address.clone()))
         {
-            run_asset_policy(&env, &num_signers, address, &signers, &fn_name, &args)
+            run_asset_policy(&env, &signed_weight, address, &signers, &fn_name, &args)
         }
     }
 }
 
-/// ## Get Core Threshold
+/// ## Bump Instance
 ///
-/// Returns the threshold for core-related actions.
+/// Extends the lifetime of the contract instance and code to a year once less than half a year is left.
+/// An entry can live for at most `BUMP_A_YEAR` ledgers counting the current one.
 ///
 /// - `env`: Environment context.
-/// - `num_signers`: Number of signers involved.
-/// - `signers`: List of signers' addresses.
-/// - `fn_name`: Function name representing the action.
-/// - `args`: Additional arguments for the action.
+fn bump_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .bump(BUMP_THRESHOLD, BUMP_A_YEAR - 1);
+}
+
+/// ## Total Weight
+///
+/// Returns the summed weight of all signers of the multiclique.
+///
+/// - `signers`: List of signers and their weights.
+///
+/// **Returns**: Total weight as a `u32`.
+fn total_weight(signers: &Vec<Signer>) -> u32 {
+    signers
+        .iter()
+        .fold(0_u32, |total, signer| total.saturating_add(signer.weight))
+}
+
+/// ## Percent Of
+///
+/// Returns `percent` percent of `total_weight`, rounded up to the next whole weight, so that
+/// e.g. 66% of 10 signers requires 7 of them and never less than the stated share.
+/// At least one signer is always required. Computed in `u64` so large weights cannot overflow.
+///
+/// - `total_weight`: Summed weight of all signers.
+/// - `percent`: Required share in percent.
 ///
 /// **Returns**: Threshold as a `u32`.
-fn get_core_threshold(
-    env: &Env,
-    num_signers: &u32,
-    _signers: &Vec<BytesN<32>>,
-    fn_name: &Symbol,
-    _args: &Vec<Val>,
-) -> u32 {
-    if fn_name == &Symbol::new(&env, "destroy_dao") || fn_name == &Symbol::new(&env, "change_owner")
-    {
-        return (num_signers * 80) / 100;
-    }
-    (num_signers * 66) / 100
+fn percent_of(total_weight: u32, percent: u32) -> u32 {
+    let scaled = total_weight as u64 * percent as u64;
+    (scaled.div_ceil(100) as u32).max(1)
 }
 
-/// ## Get Votes Threshold
+/// ## Get Rules
 ///
-/// Returns the threshold for votes-related actions.
+/// Returns the stored rules for a target contract, if any.
 ///
 /// - `env`: Environment context.
-/// - `num_signers`: Number of signers involved.
-/// - `signers`: List of signers' addresses.
-/// - `fn_name`: Function name representing the action.
-/// - `args`: Additional arguments for the action.
+/// - `address`: Target contract.
+fn get_rules(env: &Env, address: &Address) -> Option<Rules> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Rules(address.clone()))
+}
+
+/// ## Set Rules
 ///
-/// **Returns**: Threshold as a `u32`.
-fn get_votes_threshold(
-    env: &Env,
-    num_signers: &u32,
-    _signers: &Vec<BytesN<32>>,
-    fn_name: &Symbol,
-    _args: &Vec<Val>,
-) -> u32 {
-    if fn_name == &Symbol::new(&env, "fault_proposal") {
-        return 1;
-    } else if fn_name == &Symbol::new(&env, "mark_implemented") {
-        return (num_signers * 50) / 100;
-    }
-    (num_signers * 66) / 100
+/// Stores the rules for a target contract.
+///
+/// - `env`: Environment context.
+/// - `address`: Target contract.
+/// - `rules`: Rules to store.
+fn set_rules(env: &Env, address: &Address, rules: &Rules) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Rules(address.clone()), rules);
 }
 
-/// ## Get Asset Threshold
+/// ## Get Spend Limit
 ///
-/// Returns the threshold for asset-related actions.
+/// Returns the spend limit for a token, or a zero limit without a period if none is set.
 ///
 /// - `env`: Environment context.
-/// - `num_signers`: Number of signers involved.
-/// - `signers`: List of signers' addresses.
-/// - `fn_name`: Function name representing the action.
-/// - `args`: Additional arguments for the action.
+/// - `address`: Token address.
+fn get_spend_limit(env: &Env, address: &Address) -> SpendLimit {
+    env.storage()
+        .instance()
+        .get(&DataKey::SpendLimit(address.clone()))
+        .unwrap_or(SpendLimit {
+            limit: 0,
+            period: 0,
+        })
+}
+
+/// ## Current Spent
 ///
-/// **Returns**: Threshold as a `u32`.
-fn get_asset_threshold(
-    env: &Env,
-    num_signers: &u32,
-    _signers: &Vec<BytesN<32>>,
-    fn_name: &Symbol,
-    _args: &Vec<Val>,
-) -> u32 {
-    if fn_name == &Symbol::new(&env, "set_owner")
-        || fn_name == &Symbol::new(&env, "set_core_address")
-    {
-        return (num_signers * 80) / 100;
+/// Returns the amount spent of a token in the current window. Once a window has passed,
+/// the window is rolled forward by whole periods and the amount starts at zero again.
+///
+/// - `env`: Environment context.
+/// - `address`: Token address.
+/// - `spend_limit`: Spend limit of the token.
+fn current_spent(env: &Env, address: &Address, spend_limit: &SpendLimit) -> AlreadySpent {
+    let sequence = env.ledger().sequence();
+    let mut spent = env
+        .storage()
+        .instance()
+        .get(&DataKey::AlreadySpent(address.clone()))
+        .unwrap_or(AlreadySpent {
+            amount: 0,
+            window_start: sequence,
+            window_end: u32::MAX,
+        });
+
+    if spend_limit.period == 0 {
+        spent.window_end = u32::MAX;
+        return spent;
     }
-    (num_signers * 50) / 100
+    let elapsed = sequence.saturating_sub(spent.window_start);
+    if elapsed >= spend_limit.period {
+        spent.window_start += elapsed - elapsed % spend_limit.period;
+        spent.amount = 0;
+    }
+    spent.window_end = spent.window_start.saturating_add(spend_limit.period);
+    spent
 }
 
 /// ## Run Asset Policy
@@ -324,55 +574,64 @@ fn get_asset_threshold(
 /// Executes the policy rules for asset-related actions.
 ///
 /// - `env`: Environment context.
-/// - `num_signers`: Number of signers involved.
+/// - `signed_weight`: Summed weight of the signers involved.
 /// - `address`: Target address for the action.
-/// - `signers`: List of signers' addresses.
+/// - `signers`: List of signers and their weights.
 /// - `fn_name`: Function name representing the action.
 /// - `args`: Additional arguments for the action.
 fn run_asset_policy(
     env: &Env,
-    _num_signers: &u32,
+    _signed_weight: &u32,
     address: Address,
-    _signers: &Vec<BytesN<32>>,
+    _signers: &Vec<Signer>,
     fn_name: &Symbol,
     args: &Vec<Val>,
 ) {
     let contract_address: Address = env.storage().instance().get(&DataKey::MultiClique).unwrap();
 
-    let is_xfer = fn_name == &Symbol::new(&env, "xfer");
-    let is_incr_allowance = fn_name == &Symbol::new(&env, "incr_allowance");
+    // Position of the amount in the SEP-41 token functions that move value away from the
+    // authorizing account, which is always the first argument:
+    // transfer(from, to, amount), approve(from, spender, amount, expiration_ledger),
+    // burn(from, amount), transfer_from(spender, from, to, amount), burn_from(spender, from, amount)
+    let amount_index = match fn_name {
+        f if f == &Symbol::new(env, "transfer") => 2,
+        f if f == &Symbol::new(env, "approve") => 2,
+        f if f == &Symbol::new(env, "burn") => 1,
+        f if f == &Symbol::new(env, "transfer_from") => 3,
+        f if f == &Symbol::new(env, "burn_from") => 2,
+        _ => return,
+    };
+    let authorizer: Address = args.get(0).unwrap().try_into_val(env).unwrap();
+    if authorizer != contract_address {
+        return;
+    }
+    // an approved allowance counts in full, as the policy can't tell how much of a previous
+    // allowance the spender has already used
+    let amount: i128 = args.get(amount_index).unwrap().try_into_val(env).unwrap();
+    if amount < 0 {
+        panic_with_error!(&env, errors::PolicyError::InvalidAmount);
+    }
 
-    if is_xfer || is_incr_allowance {
-        let from: Address = args.get(0).unwrap().try_into_val(env).unwrap();
-        let amount: i128 = args.get(2).unwrap().try_into_val(env).unwrap();
+    let spend_limit = get_spend_limit(env, &address);
+    let mut spent = current_spent(env, &address, &spend_limit);
+    spent.amount = spent
+        .amount
+        .checked_add(amount)
+        .unwrap_or_else(|| panic_with_error!(&env, errors::PolicyError::AmountOverflow));
 
-        if from == contract_address {
-            let spend_limit = env
-                .storage()
-                .instance()
-                .get(&DataKey::SpendLimit(address.clone()))
-                .unwrap_or(0_i128);
-            let already_spent = env
-                .storage()
-                .instance()
-                .get(&DataKey::AlreadySpent(address.clone()))
-                .unwrap_or(0_i128)
-                + amount;
-
-            if already_spent > spend_limit {
-                panic_with_error!(&env, errors::PolicyError::SpendLimitExceeded);
-            }
-            env.storage()
-                .instance()
-                .set(&DataKey::AlreadySpent(address.clone()), &already_spent);
-
-            env.events().publish(
-                (POLICY, ALREADY_SPENT_UPDATE),
-                PolicyAlreadySpentUpdateEventData {
-                    address,
-                    already_spent,
-                },
-            );
-        }
+    if spent.amount > spend_limit.limit {
+        panic_with_error!(&env, errors::PolicyError::SpendLimitExceeded);
     }
+    env.storage()
+        .instance()
+        .set(&DataKey::AlreadySpent(address.clone()), &spent);
+
+    env.events().publish(
+        (POLICY, ALREADY_SPENT_UPDATE),
+        PolicyAlreadySpentUpdateEventData {
+            address,
+            already_spent: spent.amount,
+            window_end: spent.window_end,
+        },
+    );
 }
@@ -1,5 +1,13 @@
-use crate::{Contract, ContractClient};
-use soroban_sdk::{testutils::Address as _, testutils::Events as _, vec, Address, BytesN, Env, IntoVal, Symbol, Val, Vec};
+use crate::errors::PolicyError;
+use crate::{
+    AlreadySpent, Contract, ContractClient, Rules, SpendLimit, BUMP_A_YEAR, BUMP_THRESHOLD,
+};
+use commons::types::{Signer, SignerKey};
+use soroban_sdk::{
+    map, testutils::Address as _, testutils::AuthorizedFunction, testutils::Events as _,
+    testutils::Ledger as _, token, vec, xdr::Hash, xdr::LedgerKey, xdr::ScAddress, xdr::ScVal,
+    Address, Env, IntoVal, Symbol, Val, Vec,
+};
 struct Protocol {
     env: Env,
     client: ContractClient<'static>,
@@ -9,11 +17,39 @@ struct Protocol {
     asset_address: Address,
     multiclique_address: Address,
 
-    signers: Vec<BytesN<32>>,
+    signers: Vec<Signer>,
+    signed: Vec<SignerKey>,
     args: Vec<Val>,
 }
 
 impl Protocol {
+    /// Registers a token of the soroban token testutils contract and funds the multiclique.
+    fn token(&self) -> token::Client<'static> {
+        let address = self
+            .env
+            .register_stellar_asset_contract(Address::random(&self.env));
+        token::StellarAssetClient::new(&self.env, &address)
+            .mint(&self.multiclique_address, &10_000_i128);
+        token::Client::new(&self.env, &address)
+    }
+
+    /// Runs the policy for every call the multiclique authorized in the last invocation,
+    /// like its `__check_auth` would.
+    fn run_authorized(&self) {
+        let mut calls: Vec<(Address, Symbol, Vec<Val>)> = Vec::new(&self.env);
+        for (address, invocation) in self.env.auths() {
+            if address == self.multiclique_address {
+                if let AuthorizedFunction::Contract(call) = invocation.function {
+                    calls.push_back(call);
+                }
+            }
+        }
+        for (contract, fn_name, args) in calls.iter() {
+            self.client
+                .run_policy(&10, &self.signers, &self.signed, &contract, &fn_name, &args);
+        }
+    }
+
     fn new() -> Self {
         let env = Env::default();
         env.budget().reset_unlimited();
@@ -26,11 +62,15 @@ impl Protocol {
         let votes_address = Address::random(&env);
         let asset_address = Address::random(&env);
         let multiclique_address = Address::random(&env);
-        let signers = vec![
-            &env,
-            Address::random(&env).contract_id(),
-            Address::random(&env).contract_id(),
-        ];
+        let mut signers = Vec::new(&env);
+        for _ in 0..10 {
+            signers.push_back(Signer {
+                key: SignerKey::Ed25519(Address::random(&env).contract_id()),
+                weight: 1,
+                expires_at: 0,
+            });
+        }
+        let signed = vec![&env, signers.get_unchecked(0).key];
         let args = vec![&env];
 
         client.init(
@@ -48,6 +88,7 @@ impl Protocol {
             asset_address,
             multiclique_address,
             signers,
+            signed,
             args,
         }
     }
@@ -59,19 +100,144 @@ fn threshold() {
         env,
         client,
         signers,
+        signed,
         args,
         core_address,
         ..
     } = Protocol::new();
-    let num_signers = 1;
+    let signed_weight = 1;
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &core_address,
         &Symbol::new(&env, "destroy_dao"),
         &args,
     );
-    assert_eq!(threshold, 1);
+    assert_eq!(threshold, 8);
+}
+
+#[test]
+fn two_of_ten_cannot_destroy_dao() {
+    let Protocol {
+        env,
+        client,
+        signers,
+        args,
+        core_address,
+        ..
+    } = Protocol::new();
+    let signed = vec![
+        &env,
+        signers.get_unchecked(0).key,
+        signers.get_unchecked(1).key,
+    ];
+
+    let signed_weight = 2;
+    let threshold = client.get_threshold(
+        &signed_weight,
+        &signers,
+        &signed,
+        &core_address,
+        &Symbol::new(&env, "destroy_dao"),
+        &args,
+    );
+    assert_eq!(threshold, 8);
+    assert!(signed_weight < threshold);
+
+    // three signatures do not clear 66% of ten either
+    let signed_weight = 3;
+    let threshold = client.get_threshold(
+        &signed_weight,
+        &signers,
+        &signed,
+        &core_address,
+        &Symbol::new(&env, "something"),
+        &args,
+    );
+    assert_eq!(threshold, 7);
+    assert!(signed_weight < threshold);
+}
+
+#[test]
+fn threshold_rounds_up() {
+    let Protocol {
+        env,
+        client,
+        signed,
+        args,
+        core_address,
+        votes_address,
+        ..
+    } = Protocol::new();
+    let signers = vec![
+        &env,
+        Signer {
+            key: SignerKey::Ed25519(Address::random(&env).contract_id()),
+            weight: 2,
+            expires_at: 0,
+        },
+        Signer {
+            key: SignerKey::Ed25519(Address::random(&env).contract_id()),
+            weight: 1,
+            expires_at: 0,
+        },
+    ];
+
+    // 80% of 3 is 2.4
+    let threshold = client.get_threshold(
+        &1,
+        &signers,
+        &signed,
+        &core_address,
+        &Symbol::new(&env, "destroy_dao"),
+        &args,
+    );
+    assert_eq!(threshold, 3);
+
+    // 66% of 3 is 1.98
+    let threshold = client.get_threshold(
+        &1,
+        &signers,
+        &signed,
+        &core_address,
+        &Symbol::new(&env, "something"),
+        &args,
+    );
+    assert_eq!(threshold, 2);
+
+    // 50% of 3 is 1.5
+    let threshold = client.get_threshold(
+        &1,
+        &signers,
+        &signed,
+        &votes_address,
+        &Symbol::new(&env, "mark_implemented"),
+        &args,
+    );
+    assert_eq!(threshold, 2);
+}
+
+#[test]
+fn unknown_contract_requires_all_signers() {
+    let Protocol {
+        env,
+        client,
+        signers,
+        signed,
+        args,
+        ..
+    } = Protocol::new();
+
+    let threshold = client.get_threshold(
+        &10,
+        &signers,
+        &signed,
+        &Address::random(&env),
+        &Symbol::new(&env, "something"),
+        &args,
+    );
+    assert_eq!(threshold, 10);
 }
 
 #[test]
@@ -80,15 +246,17 @@ fn core_threshold() {
         env,
         client,
         signers,
+        signed,
         args,
         core_address,
         ..
     } = Protocol::new();
 
-    let num_signers = 10;
+    let signed_weight = 10;
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &core_address,
         &Symbol::new(&env, "destroy_dao"),
         &args,
@@ -96,8 +264,9 @@ fn core_threshold() {
     assert_eq!(threshold, 8);
 
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &core_address,
         &Symbol::new(&env, "change_owner"),
         &args,
@@ -105,13 +274,14 @@ fn core_threshold() {
     assert_eq!(threshold, 8);
 
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &core_address,
         &Symbol::new(&env, "something"),
         &args,
     );
-    assert_eq!(threshold, 6);
+    assert_eq!(threshold, 7);
 }
 
 #[test]
@@ -120,15 +290,17 @@ fn votes_threshold() {
         env,
         client,
         signers,
+        signed,
         args,
         votes_address,
         ..
     } = Protocol::new();
 
-    let num_signers = 10;
+    let signed_weight = 10;
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &votes_address,
         &Symbol::new(&env, "fault_proposal"),
         &args,
@@ -136,8 +308,9 @@ fn votes_threshold() {
     assert_eq!(threshold, 1);
 
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &votes_address,
         &Symbol::new(&env, "mark_implemented"),
         &args,
@@ -145,13 +318,14 @@ fn votes_threshold() {
     assert_eq!(threshold, 5);
 
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &votes_address,
         &Symbol::new(&env, "something"),
         &args,
     );
-    assert_eq!(threshold, 6);
+    assert_eq!(threshold, 7);
 }
 
 #[test]
@@ -160,15 +334,17 @@ fn assets_threshold() {
         env,
         client,
         signers,
+        signed,
         args,
         asset_address,
         ..
     } = Protocol::new();
 
-    let num_signers = 10;
+    let signed_weight = 10;
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &asset_address,
         &Symbol::new(&env, "set_owner"),
         &args,
@@ -176,8 +352,9 @@ fn assets_threshold() {
     assert_eq!(threshold, 8);
 
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &asset_address,
         &Symbol::new(&env, "set_core_address"),
         &args,
@@ -185,8 +362,9 @@ fn assets_threshold() {
     assert_eq!(threshold, 8);
 
     let threshold = client.get_threshold(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &asset_address,
         &Symbol::new(&env, "something"),
         &args,
@@ -197,46 +375,57 @@ fn assets_threshold() {
 #[test]
 #[should_panic(expected = "Error(Contract, #1101)")]
 fn test_spend_limit() {
-    let num_signers = 10;
+    let signed_weight = 10;
     let Protocol {
         env,
         client,
         signers,
+        signed,
         asset_address,
         multiclique_address,
         ..
     } = Protocol::new();
-    let args = ((multiclique_address), (), 400_i128).into_val(&env);
+    let args = ((multiclique_address.clone()), (), 400_i128).into_val(&env);
+    let burn_args = ((multiclique_address), 400_i128).into_val(&env);
     assert_eq!(env.events().all().len(), 1);
 
-    client.set_spend_limit(&asset_address, &1000_i128);
+    client.set_spend_limit(&asset_address, &1000_i128, &0);
     assert_eq!(env.events().all().len(), 2);
-    assert_eq!(client.get_spend_limit(&asset_address), 1000_i128);
-    assert_eq!(client.get_already_spent(&asset_address), 0_i128);
+    assert_eq!(
+        client.get_spend_limit(&asset_address),
+        Some(SpendLimit {
+            limit: 1000_i128,
+            period: 0
+        })
+    );
+    assert_eq!(client.get_already_spent(&asset_address).amount, 0_i128);
 
     client.run_policy(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &asset_address,
-        &Symbol::new(&env, "xfer"),
+        &Symbol::new(&env, "transfer"),
         &args,
     );
-    assert_eq!(client.get_already_spent(&asset_address), 400_i128);
+    assert_eq!(client.get_already_spent(&asset_address).amount, 400_i128);
     client.run_policy(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &asset_address,
-        &Symbol::new(&env, "incr_allowance"),
-        &args,
+        &Symbol::new(&env, "burn"),
+        &burn_args,
     );
-    assert_eq!(client.get_already_spent(&asset_address), 800_i128);
+    assert_eq!(client.get_already_spent(&asset_address).amount, 800_i128);
     assert_eq!(env.events().all().len(), 4);
     // exceeds limit!
     client.run_policy(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &asset_address,
-        &Symbol::new(&env, "xfer"),
+        &Symbol::new(&env, "transfer"),
         &args,
     );
 }
@@ -247,38 +436,655 @@ fn test_reset_spend_limit() {
         env,
         client,
         signers,
+        signed,
         asset_address,
         multiclique_address,
         ..
     } = Protocol::new();
     assert_eq!(env.events().all().len(), 1);
-    let num_signers = 10;
-    client.set_spend_limit(&asset_address, &1000_i128);
+    let signed_weight = 10;
+    client.set_spend_limit(&asset_address, &1000_i128, &0);
     assert_eq!(env.events().all().len(), 2);
-    let args = ((multiclique_address), (), 400_i128).into_val(&env);
+    let args = ((multiclique_address.clone()), (), 400_i128).into_val(&env);
+    let burn_args = ((multiclique_address), 400_i128).into_val(&env);
 
-    assert_eq!(client.get_spend_limit(&asset_address), 1000_i128);
-    assert_eq!(client.get_already_spent(&asset_address), 0_i128);
+    assert_eq!(
+        client.get_spend_limit(&asset_address),
+        Some(SpendLimit {
+            limit: 1000_i128,
+            period: 0
+        })
+    );
+    assert_eq!(client.get_already_spent(&asset_address).amount, 0_i128);
 
     client.run_policy(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &asset_address,
-        &Symbol::new(&env, "xfer"),
+        &Symbol::new(&env, "transfer"),
         &args,
     );
-    assert_eq!(client.get_already_spent(&asset_address), 400_i128);
+    assert_eq!(client.get_already_spent(&asset_address).amount, 400_i128);
 
     client.run_policy(
-        &num_signers,
+        &signed_weight,
         &signers,
+        &signed,
         &asset_address,
-        &Symbol::new(&env, "incr_allowance"),
-        &args,
+        &Symbol::new(&env, "burn"),
+        &burn_args,
     );
-    assert_eq!(client.get_already_spent(&asset_address), 800_i128);
+    assert_eq!(client.get_already_spent(&asset_address).amount, 800_i128);
 
     client.reset_spend_limit(&asset_address);
-    assert_eq!(client.get_already_spent(&asset_address), 0_i128);
+    assert_eq!(client.get_already_spent(&asset_address).amount, 0_i128);
     assert_eq!(env.events().all().len(), 5);
 }
+
+/// Returns the last ledger in which the instance of a contract is live.
+fn instance_expiration(env: &Env, contract: &Address) -> u32 {
+    let contract = ScAddress::Contract(Hash(contract.contract_id().to_array()));
+    env.to_snapshot()
+        .ledger_entries
+        .into_iter()
+        .find_map(|(key, (_, expiration))| match *key {
+            LedgerKey::ContractData(data)
+                if data.contract == contract && data.key == ScVal::LedgerKeyContractInstance =>
+            {
+                expiration
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_extend_ttl() {
+    let Protocol {
+        env,
+        client,
+        asset_address,
+        ..
+    } = Protocol::new();
+    env.ledger()
+        .with_mut(|li| li.sequence_number += BUMP_THRESHOLD + 1);
+    client.set_spend_limit(&asset_address, &1000_i128, &0);
+    assert_eq!(
+        instance_expiration(&env, &client.address),
+        env.ledger().sequence() + BUMP_A_YEAR - 1
+    );
+
+    env.ledger()
+        .with_mut(|li| li.sequence_number += BUMP_THRESHOLD + 1);
+    client.extend_ttl();
+    assert_eq!(
+        instance_expiration(&env, &client.address),
+        env.ledger().sequence() + BUMP_A_YEAR - 1
+    );
+    assert_eq!(
+        client.get_spend_limit(&asset_address),
+        Some(SpendLimit {
+            limit: 1000_i128,
+            period: 0
+        })
+    );
+}
+
+#[test]
+fn test_init_seeds_rules() {
+    let Protocol {
+        env,
+        client,
+        core_address,
+        ..
+    } = Protocol::new();
+
+    assert_eq!(
+        client.get_rules(&core_address),
+        Some(Rules {
+            default: 66,
+            functions: map![
+                &env,
+                (Symbol::new(&env, "destroy_dao"), 80),
+                (Symbol::new(&env, "change_owner"), 80)
+            ],
+        })
+    );
+    assert_eq!(client.get_rules(&Address::random(&env)), None);
+}
+
+#[test]
+fn test_set_rule() {
+    let Protocol {
+        env,
+        client,
+        signers,
+        signed,
+        args,
+        core_address,
+        ..
+    } = Protocol::new();
+    let destroy_dao = Symbol::new(&env, "destroy_dao");
+
+    client.set_rule(&core_address, &Some(destroy_dao.clone()), &100);
+    assert_eq!(env.events().all().len(), 2);
+    assert_eq!(
+        client.get_threshold(&10, &signers, &signed, &core_address, &destroy_dao, &args),
+        10
+    );
+
+    client.set_rule(&core_address, &None, &30);
+    assert_eq!(
+        client.get_threshold(
+            &10,
+            &signers,
+            &signed,
+            &core_address,
+            &Symbol::new(&env, "something"),
+            &args
+        ),
+        3
+    );
+
+    // rules for a contract unknown to the preset
+    let target = Address::random(&env);
+    client.set_rule(&target, &Some(Symbol::new(&env, "ping")), &20);
+    assert_eq!(
+        client.get_rules(&target),
+        Some(Rules {
+            default: 100,
+            functions: map![&env, (Symbol::new(&env, "ping"), 20)],
+        })
+    );
+    assert_eq!(
+        client.get_threshold(
+            &10,
+            &signers,
+            &signed,
+            &target,
+            &Symbol::new(&env, "ping"),
+            &args
+        ),
+        2
+    );
+    assert_eq!(
+        client.get_threshold(
+            &10,
+            &signers,
+            &signed,
+            &target,
+            &Symbol::new(&env, "pong"),
+            &args
+        ),
+        10
+    );
+}
+
+#[test]
+fn test_set_rule_fails_if_invalid() {
+    let Protocol {
+        env,
+        client,
+        core_address,
+        ..
+    } = Protocol::new();
+
+    assert_eq!(
+        client.try_set_rule(&core_address, &None, &101),
+        Err(Ok(PolicyError::InvalidRule.into()))
+    );
+    assert_eq!(env.events().all().len(), 1);
+}
+
+#[test]
+fn test_remove_rule() {
+    let Protocol {
+        env,
+        client,
+        signers,
+        signed,
+        args,
+        core_address,
+        ..
+    } = Protocol::new();
+    let destroy_dao = Symbol::new(&env, "destroy_dao");
+
+    client.remove_rule(&core_address, &Some(destroy_dao.clone()));
+    assert_eq!(env.events().all().len(), 2);
+    // falls back to the default of the target
+    assert_eq!(
+        client.get_threshold(&10, &signers, &signed, &core_address, &destroy_dao, &args),
+        7
+    );
+
+    client.remove_rule(&core_address, &None);
+    assert_eq!(
+        client.get_threshold(&10, &signers, &signed, &core_address, &destroy_dao, &args),
+        10
+    );
+
+    client.remove_rule(&core_address, &Some(Symbol::new(&env, "change_owner")));
+    assert_eq!(client.get_rules(&core_address), None);
+    assert_eq!(env.events().all().len(), 4);
+}
+
+#[test]
+fn test_remove_rule_fails_if_not_exists() {
+    let Protocol {
+        env,
+        client,
+        core_address,
+        ..
+    } = Protocol::new();
+
+    assert_eq!(
+        client.try_remove_rule(&core_address, &Some(Symbol::new(&env, "something"))),
+        Err(Ok(PolicyError::RuleDoesNotExist.into()))
+    );
+    assert_eq!(
+        client.try_remove_rule(&Address::random(&env), &None),
+        Err(Ok(PolicyError::RuleDoesNotExist.into()))
+    );
+}
+
+#[test]
+fn test_rules_require_multiclique_auth() {
+    let Protocol {
+        env,
+        client,
+        core_address,
+        multiclique_address,
+        ..
+    } = Protocol::new();
+
+    client.set_rule(&core_address, &None, &50);
+    assert_eq!(env.auths()[0].0, multiclique_address);
+    client.remove_rule(&core_address, &None);
+    assert_eq!(env.auths()[0].0, multiclique_address);
+}
+
+#[test]
+fn test_spend_limit_window() {
+    let Protocol {
+        env,
+        client,
+        signers,
+        signed,
+        asset_address,
+        multiclique_address,
+        ..
+    } = Protocol::new();
+    let args = ((multiclique_address), (), 800_i128).into_val(&env);
+    let transfer = Symbol::new(&env, "transfer");
+    let start = env.ledger().sequence();
+
+    client.set_spend_limit(&asset_address, &1000_i128, &100);
+    assert_eq!(
+        client.get_already_spent(&asset_address),
+        AlreadySpent {
+            amount: 0,
+            window_start: start,
+            window_end: start + 100,
+        }
+    );
+
+    client.run_policy(&10, &signers, &signed, &asset_address, &transfer, &args);
+    assert_eq!(client.get_already_spent(&asset_address).amount, 800_i128);
+    assert!(client
+        .try_run_policy(&10, &signers, &signed, &asset_address, &transfer, &args)
+        .is_err());
+
+    // last ledger of the window
+    env.ledger().with_mut(|li| li.sequence_number += 99);
+    assert_eq!(client.get_already_spent(&asset_address).amount, 800_i128);
+
+    // the next window starts without any reset
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    assert_eq!(
+        client.get_already_spent(&asset_address),
+        AlreadySpent {
+            amount: 0,
+            window_start: start + 100,
+            window_end: start + 200,
+        }
+    );
+    client.run_policy(&10, &signers, &signed, &asset_address, &transfer, &args);
+    assert_eq!(client.get_already_spent(&asset_address).amount, 800_i128);
+
+    // windows stay aligned after idle periods
+    env.ledger().with_mut(|li| li.sequence_number += 250);
+    client.run_policy(&10, &signers, &signed, &asset_address, &transfer, &args);
+    assert_eq!(
+        client.get_already_spent(&asset_address),
+        AlreadySpent {
+            amount: 800,
+            window_start: start + 300,
+            window_end: start + 400,
+        }
+    );
+}
+
+#[test]
+fn test_spend_limit_without_period() {
+    let Protocol {
+        env,
+        client,
+        signers,
+        signed,
+        asset_address,
+        multiclique_address,
+        ..
+    } = Protocol::new();
+    let args = ((multiclique_address), (), 800_i128).into_val(&env);
+
+    client.set_spend_limit(&asset_address, &1000_i128, &0);
+    client.run_policy(
+        &10,
+        &signers,
+        &signed,
+        &asset_address,
+        &Symbol::new(&env, "transfer"),
+        &args,
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 1_000_000);
+    let spent = client.get_already_spent(&asset_address);
+    assert_eq!(spent.amount, 800_i128);
+    assert_eq!(spent.window_end, u32::MAX);
+}
+
+#[test]
+fn test_spend_limit_transfer() {
+    let protocol = Protocol::new();
+    let token = protocol.token();
+    let to = Address::random(&protocol.env);
+    protocol
+        .client
+        .set_spend_limit(&token.address, &1000_i128, &0);
+
+    token.transfer(&protocol.multiclique_address, &to, &600_i128);
+    protocol.run_authorized();
+    assert_eq!(
+        protocol.client.get_already_spent(&token.address).amount,
+        600_i128
+    );
+
+    // transfers of other accounts are not counted
+    token.transfer(&to, &protocol.multiclique_address, &100_i128);
+    protocol.run_authorized();
+    assert_eq!(
+        protocol.client.get_already_spent(&token.address).amount,
+        600_i128
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #1101)")]
+fn test_spend_limit_transfer_exceeded() {
+    let protocol = Protocol::new();
+    let token = protocol.token();
+    protocol
+        .client
+        .set_spend_limit(&token.address, &1000_i128, &0);
+
+    token.transfer(
+        &protocol.multiclique_address,
+        &Address::random(&protocol.env),
+        &1001_i128,
+    );
+    protocol.run_authorized();
+}
+
+#[test]
+fn test_spend_limit_approve() {
+    let protocol = Protocol::new();
+    let token = protocol.token();
+    let spender = Address::random(&protocol.env);
+    let expiration_ledger = protocol.env.ledger().sequence() + 100;
+    protocol
+        .client
+        .set_spend_limit(&token.address, &1000_i128, &0);
+
+    token.approve(
+        &protocol.multiclique_address,
+        &spender,
+        &500_i128,
+        &expiration_ledger,
+    );
+    protocol.run_authorized();
+    assert_eq!(
+        protocol.client.get_already_spent(&token.address).amount,
+        500_i128
+    );
+
+    // every approval counts in full, revoking is free
+    token.approve(
+        &protocol.multiclique_address,
+        &spender,
+        &300_i128,
+        &expiration_ledger,
+    );
+    protocol.run_authorized();
+    token.approve(
+        &protocol.multiclique_address,
+        &spender,
+        &0_i128,
+        &expiration_ledger,
+    );
+    protocol.run_authorized();
+    assert_eq!(
+        protocol.client.get_already_spent(&token.address).amount,
+        800_i128
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #1101)")]
+fn test_spend_limit_approve_after_transfer_from() {
+    let protocol = Protocol::new();
+    let token = protocol.token();
+    let spender = Address::random(&protocol.env);
+    let expiration_ledger = protocol.env.ledger().sequence() + 100;
+    protocol
+        .client
+        .set_spend_limit(&token.address, &1000_i128, &0);
+
+    token.approve(
+        &protocol.multiclique_address,
+        &spender,
+        &600_i128,
+        &expiration_ledger,
+    );
+    protocol.run_authorized();
+    token.transfer_from(
+        &spender,
+        &protocol.multiclique_address,
+        &Address::random(&protocol.env),
+        &600_i128,
+    );
+    assert_eq!(token.allowance(&protocol.multiclique_address, &spender), 0);
+
+    // approving the used up allowance again would spend past the limit
+    token.approve(
+        &protocol.multiclique_address,
+        &spender,
+        &600_i128,
+        &expiration_ledger,
+    );
+    protocol.run_authorized();
+}
+
+#[test]
+fn test_spend_limit_burn() {
+    let protocol = Protocol::new();
+    let token = protocol.token();
+    protocol
+        .client
+        .set_spend_limit(&token.address, &1000_i128, &0);
+
+    token.burn(&protocol.multiclique_address, &250_i128);
+    protocol.run_authorized();
+    assert_eq!(
+        protocol.client.get_already_spent(&token.address).amount,
+        250_i128
+    );
+}
+
+#[test]
+fn test_spend_limit_transfer_from() {
+    let protocol = Protocol::new();
+    let token = protocol.token();
+    let owner = Address::random(&protocol.env);
+    let expiration_ledger = protocol.env.ledger().sequence() + 100;
+    protocol
+        .client
+        .set_spend_limit(&token.address, &1000_i128, &0);
+    token.transfer(&protocol.multiclique_address, &owner, &500_i128);
+    token.approve(
+        &owner,
+        &protocol.multiclique_address,
+        &500_i128,
+        &expiration_ledger,
+    );
+
+    token.transfer_from(
+        &protocol.multiclique_address,
+        &owner,
+        &Address::random(&protocol.env),
+        &300_i128,
+    );
+    protocol.run_authorized();
+    assert_eq!(
+        protocol.client.get_already_spent(&token.address).amount,
+        300_i128
+    );
+
+    token.burn_from(&protocol.multiclique_address, &owner, &150_i128);
+    protocol.run_authorized();
+    assert_eq!(
+        protocol.client.get_already_spent(&token.address).amount,
+        450_i128
+    );
+}
+
+#[test]
+fn test_spend_limit_ignores_other_functions() {
+    let protocol = Protocol::new();
+    // the multiclique administers this token, so it authorizes minting
+    let address = protocol
+        .env
+        .register_stellar_asset_contract(protocol.multiclique_address.clone());
+    protocol.client.set_spend_limit(&address, &1000_i128, &0);
+
+    token::StellarAssetClient::new(&protocol.env, &address)
+        .mint(&Address::random(&protocol.env), &5000_i128);
+    assert_eq!(protocol.env.auths()[0].0, protocol.multiclique_address);
+    protocol.run_authorized();
+    assert_eq!(protocol.client.get_already_spent(&address).amount, 0_i128);
+}
+
+#[test]
+fn test_set_spend_limit_fails_if_negative() {
+    let Protocol {
+        env,
+        client,
+        asset_address,
+        ..
+    } = Protocol::new();
+
+    assert_eq!(
+        client.try_set_spend_limit(&asset_address, &-1_i128, &0),
+        Err(Ok(PolicyError::InvalidSpendLimit.into()))
+    );
+    assert_eq!(client.get_spend_limit(&asset_address), None);
+    assert_eq!(env.events().all().len(), 1);
+}
+
+#[test]
+fn test_spend_limit_rejects_negative_amounts() {
+    let Protocol {
+        env,
+        client,
+        signers,
+        signed,
+        asset_address,
+        multiclique_address,
+        ..
+    } = Protocol::new();
+    client.set_spend_limit(&asset_address, &1000_i128, &0);
+    client.run_policy(
+        &10,
+        &signers,
+        &signed,
+        &asset_address,
+        &Symbol::new(&env, "transfer"),
+        &((multiclique_address.clone()), (), 800_i128).into_val(&env),
+    );
+
+    // a negative transfer must not lower the amount spent
+    assert_eq!(
+        client.try_run_policy(
+            &10,
+            &signers,
+            &signed,
+            &asset_address,
+            &Symbol::new(&env, "transfer"),
+            &((multiclique_address.clone()), (), -800_i128).into_val(&env),
+        ),
+        Err(Ok(PolicyError::InvalidAmount.into()))
+    );
+    assert_eq!(
+        client.try_run_policy(
+            &10,
+            &signers,
+            &signed,
+            &asset_address,
+            &Symbol::new(&env, "approve"),
+            &(
+                (multiclique_address),
+                Address::random(&env),
+                -800_i128,
+                env.ledger().sequence() + 100
+            )
+                .into_val(&env),
+        ),
+        Err(Ok(PolicyError::InvalidAmount.into()))
+    );
+    assert_eq!(client.get_already_spent(&asset_address).amount, 800_i128);
+}
+
+#[test]
+fn test_spend_limit_rejects_overflow() {
+    let Protocol {
+        env,
+        client,
+        signers,
+        signed,
+        asset_address,
+        multiclique_address,
+        ..
+    } = Protocol::new();
+    client.set_spend_limit(&asset_address, &i128::MAX, &0);
+    client.run_policy(
+        &10,
+        &signers,
+        &signed,
+        &asset_address,
+        &Symbol::new(&env, "transfer"),
+        &((multiclique_address.clone()), (), i128::MAX).into_val(&env),
+    );
+
+    assert_eq!(
+        client.try_run_policy(
+            &10,
+            &signers,
+            &signed,
+            &asset_address,
+            &Symbol::new(&env, "transfer"),
+            &((multiclique_address), (), 1_i128).into_val(&env),
+        ),
+        Err(Ok(PolicyError::AmountOverflow.into()))
+    );
+    assert_eq!(client.get_already_spent(&asset_address).amount, i128::MAX);
+}